@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use crate::{Direction, Point};
+
+/// Shortest distances from one start to every reachable cell of a grid where each
+/// step to a neighbouring cell costs one.
+#[derive(Debug, Clone)]
+pub struct GridDistances {
+    size: (usize, usize),
+    start: Point,
+    distance: Vec<Option<usize>>,
+    came_from: Vec<Option<Point>>,
+}
+
+/// Breadth-first search from `start`, only stepping onto cells for which `passable` holds.
+pub fn grid_bfs<F>(size: (usize, usize), start: Point, passable: F) -> GridDistances
+    where F: Fn(Point) -> bool
+{
+    let mut found = GridDistances {
+        size,
+        start,
+        distance: vec![None; size.0 * size.1],
+        came_from: vec![None; size.0 * size.1],
+    };
+
+    let mut queue = VecDeque::from([start]);
+    let idx = found.index(start);
+    found.distance[idx] = Some(0);
+
+    while let Some(pos) = queue.pop_front() {
+        let dist = found.distance[found.index(pos)].unwrap();
+
+        for next in Direction::all().filter_map(|dir| pos.advance(dir, size)) {
+            let idx = found.index(next);
+            if found.distance[idx].is_none() && passable(next) {
+                found.distance[idx] = Some(dist + 1);
+                found.came_from[idx] = Some(pos);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    found
+}
+
+impl GridDistances {
+    pub fn distance(&self, pos: Point) -> Option<usize> {
+        self.distance[self.index(pos)]
+    }
+
+    /// One of the shortest paths from the start to `end`, both included.
+    pub fn path_to(&self, end: Point) -> Option<Vec<Point>> {
+        self.distance(end)?;
+
+        let mut path = vec![end];
+        let mut pos = end;
+        while pos != self.start {
+            pos = self.came_from[self.index(pos)].unwrap();
+            path.push(pos);
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    fn index(&self, pos: Point) -> usize {
+        pos.y * self.size.0 + pos.x
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::Grid;
+    use super::*;
+
+    #[test]
+    fn test_grid_bfs() {
+        let rows = [
+            "..#.",
+            ".##.",
+            "....",
+            "#.#.",
+        ];
+        let grid = Grid::try_from_lines(rows.iter().map(|r| Ok(r.to_string()))).unwrap();
+        let p = |x, y| Point { x, y };
+
+        let found = grid_bfs(grid.size(), p(0, 0), |pos| grid[pos] != '#');
+
+        assert_eq!(found.distance(p(3, 0)), Some(7));
+        assert_eq!(found.distance(p(1, 3)), Some(4));
+        assert_eq!(found.distance(p(2, 0)), None);
+        assert_eq!(found.distance(p(0, 3)), None);
+        assert_eq!(found.path_to(p(0, 0)), Some(vec![p(0, 0)]));
+        assert_eq!(found.path_to(p(0, 3)), None);
+
+        let path = found.path_to(p(3, 0)).unwrap();
+        assert_eq!(path, vec![p(0, 0), p(0, 1), p(0, 2), p(1, 2), p(2, 2), p(3, 2), p(3, 1), p(3, 0)]);
+    }
+}
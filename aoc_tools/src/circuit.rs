@@ -0,0 +1,380 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeOp {
+    Const(bool),
+    Not(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    Xor(usize, usize),
+    Nand(usize, usize),
+    Nor(usize, usize),
+    Xnor(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub op: NodeOp,
+}
+
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    names: Box<[String]>,
+    index: HashMap<String, usize>,
+    nodes: HashMap<usize, Node>,
+}
+
+impl NodeOp {
+    pub fn binary(op_name: &str, a0: usize, a1: usize) -> Option<Self> {
+        match op_name {
+            "AND" => Some(Self::And(a0, a1)),
+            "OR" => Some(Self::Or(a0, a1)),
+            "XOR" => Some(Self::Xor(a0, a1)),
+            "NAND" => Some(Self::Nand(a0, a1)),
+            "NOR" => Some(Self::Nor(a0, a1)),
+            "XNOR" => Some(Self::Xnor(a0, a1)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Const(_) => "CONST",
+            Self::Not(_) => "NOT",
+            Self::And(_, _) => "AND",
+            Self::Or(_, _) => "OR",
+            Self::Xor(_, _) => "XOR",
+            Self::Nand(_, _) => "NAND",
+            Self::Nor(_, _) => "NOR",
+            Self::Xnor(_, _) => "XNOR",
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<usize> {
+        match *self {
+            Self::Const(_) => vec![],
+            Self::Not(a0) => vec![a0],
+            Self::And(a0, a1)
+            | Self::Or(a0, a1)
+            | Self::Xor(a0, a1)
+            | Self::Nand(a0, a1)
+            | Self::Nor(a0, a1)
+            | Self::Xnor(a0, a1) => vec![a0, a1],
+        }
+    }
+
+    pub fn has_input(&self, wire: usize) -> bool {
+        self.inputs().contains(&wire)
+    }
+
+    pub fn eval<F>(&self, fetch: F) -> Option<bool>
+        where F: Fn(usize) -> Option<bool>
+    {
+        match *self {
+            Self::Const(val) => Some(val),
+            Self::Not(a0) => Some(!fetch(a0)?),
+            Self::And(a0, a1) => Some(fetch(a0)? && fetch(a1)?),
+            Self::Or(a0, a1) => Some(fetch(a0)? || fetch(a1)?),
+            Self::Xor(a0, a1) => Some(fetch(a0)? ^ fetch(a1)?),
+            Self::Nand(a0, a1) => Some(!(fetch(a0)? && fetch(a1)?)),
+            Self::Nor(a0, a1) => Some(!(fetch(a0)? || fetch(a1)?)),
+            Self::Xnor(a0, a1) => Some(!(fetch(a0)? ^ fetch(a1)?)),
+        }
+    }
+}
+
+impl Circuit {
+    pub fn new(names: Box<[String]>, nodes: HashMap<usize, Node>) -> Self {
+        let index = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.clone(), i))
+            .collect();
+
+        Self {
+            names,
+            index,
+            nodes,
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self, wire: usize) -> &str {
+        &self.names[wire]
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn nodes(&self) -> &HashMap<usize, Node> {
+        &self.nodes
+    }
+
+    pub fn node(&self, wire: usize) -> Option<&Node> {
+        self.nodes.get(&wire)
+    }
+
+    /// Exchanges the gates driving two wires, keeping the wire names in place.
+    pub fn swap_outputs(&mut self, w1: usize, w2: usize) {
+        let op1 = self.nodes[&w1].op;
+        let op2 = self.nodes[&w2].op;
+        self.nodes.get_mut(&w1).unwrap().op = op2;
+        self.nodes.get_mut(&w2).unwrap().op = op1;
+    }
+
+    /// Wires named `prefix` followed by a number (`z00`, `z01`, ...), ordered
+    /// by that number, so index 0 is the least significant bit.
+    pub fn bus(&self, prefix: &str) -> Vec<usize> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| {
+                let suffix = n.strip_prefix(prefix)?;
+                let bit: usize = suffix.parse().ok()?;
+                suffix.chars().all(|c| c.is_ascii_digit()).then_some((bit, i))
+            })
+            .sorted()
+            .map(|(_, i)| i)
+            .collect()
+    }
+
+    /// Blank wire state, ready to be filled by `int_to_wires` and `evaluate`.
+    pub fn wires(&self) -> Vec<Option<bool>> {
+        vec![None; self.names.len()]
+    }
+
+    /// Gates ordered so that every gate comes after the gates driving its inputs.
+    pub fn topological_order(&self) -> Result<Vec<usize>, CircuitCycle> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Open,
+            Done,
+        }
+
+        let mut marks = vec![Mark::New; self.names.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        for start in self.nodes.keys().sorted() {
+            if marks[*start] != Mark::New {
+                continue;
+            }
+
+            // iterative DFS, each stack entry holds the wire and its pending inputs
+            let mut stack = vec![(*start, self.driver_inputs(*start))];
+            marks[*start] = Mark::Open;
+
+            while let Some((wire, pending)) = stack.last_mut() {
+                if let Some(input) = pending.pop() {
+                    match marks[input] {
+                        Mark::New => {
+                            marks[input] = Mark::Open;
+                            stack.push((input, self.driver_inputs(input)));
+                        },
+                        Mark::Open => {
+                            let loop_start = stack
+                                .iter()
+                                .position(|(w, _)| *w == input)
+                                .unwrap();
+                            let wires = stack[loop_start..]
+                                .iter()
+                                .map(|(w, _)| self.names[*w].clone())
+                                .collect();
+                            return Err(CircuitCycle(wires));
+                        },
+                        Mark::Done => {},
+                    }
+                } else {
+                    let wire = *wire;
+                    marks[wire] = Mark::Done;
+                    if self.nodes.contains_key(&wire) {
+                        order.push(wire);
+                    }
+                    stack.pop();
+                }
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Computes every wire not yet set in `values`. Wires set beforehand (e.g. by
+    /// `int_to_wires`) take precedence over their constant drivers.
+    pub fn evaluate(&self, values: &mut [Option<bool>]) -> Result<(), CircuitCycle> {
+        self.evaluate_in(&self.topological_order()?, values);
+
+        Ok(())
+    }
+
+    /// Like `evaluate`, with an order from `topological_order` computed beforehand,
+    /// for evaluating the same circuit many times.
+    pub fn evaluate_in(&self, order: &[usize], values: &mut [Option<bool>]) {
+        for wire in order {
+            if values[*wire].is_none() {
+                values[*wire] = self.nodes[wire].op.eval(|v| values[v]);
+            }
+        }
+    }
+
+    fn driver_inputs(&self, wire: usize) -> Vec<usize> {
+        self.nodes
+            .get(&wire)
+            .map(|n| n.op.inputs())
+            .unwrap_or_default()
+    }
+}
+
+pub fn wires_to_int(values: &[Option<bool>], bus: &[usize]) -> u64 {
+    let mut result: u64 = 0;
+
+    for wire in bus.iter().rev() {
+        result <<= 1;
+        if values[*wire] == Some(true) {
+            result |= 1;
+        }
+    }
+    result
+}
+
+pub fn int_to_wires(values: &mut [Option<bool>], bus: &[usize], mut value: u64) {
+    for wire in bus {
+        values[*wire] = Some((value & 1) != 0);
+        value >>= 1;
+    }
+}
+
+
+#[derive(Debug, PartialEq)]
+pub struct CircuitCycle(pub Vec<String>);
+
+impl fmt::Display for CircuitCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circuit contains a loop: {}", self.0.join(" -> "))
+    }
+}
+
+impl std::error::Error for CircuitCycle { }
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use crate::NameRegistry;
+    use super::*;
+
+    fn build(gates: &[(&str, &str, &str, &str)]) -> Circuit {
+        let mut name_reg = NameRegistry::new();
+        let mut nodes = HashMap::new();
+
+        for (a0, op_name, a1, target) in gates {
+            let a0 = name_reg.add_or_lookup(a0);
+            let a1 = name_reg.add_or_lookup(a1);
+            let id = name_reg.add_or_lookup(target);
+            let op = if *op_name == "NOT" {
+                NodeOp::Not(a0)
+            } else {
+                NodeOp::binary(op_name, a0, a1).unwrap()
+            };
+            nodes.insert(id, Node { op });
+        }
+
+        let names: Vec<String> = name_reg.into();
+        Circuit::new(names.into_boxed_slice(), nodes)
+    }
+
+    #[rstest]
+    #[case("AND", [false, false, false, true])]
+    #[case("OR", [false, true, true, true])]
+    #[case("XOR", [false, true, true, false])]
+    #[case("NAND", [true, true, true, false])]
+    #[case("NOR", [true, false, false, false])]
+    #[case("XNOR", [true, false, false, true])]
+    fn test_binary_gates(#[case] op_name: &str, #[case] expected: [bool; 4]) {
+        let op = NodeOp::binary(op_name, 0, 1).unwrap();
+
+        for (i, exp) in expected.iter().enumerate() {
+            let inputs = [i & 1 != 0, i & 2 != 0];
+            assert_eq!(Some(*exp), op.eval(|w| Some(inputs[w])));
+        }
+    }
+
+    #[test]
+    fn test_evaluate_out_of_order() -> anyhow::Result<()> {
+        // half adder with an inverted carry, gates listed output-first
+        let circuit = build(&[
+            ("c", "NOT", "c", "z01"),
+            ("x00", "AND", "y00", "c"),
+            ("x00", "XOR", "y00", "z00"),
+        ]);
+
+        let x = circuit.bus("x");
+        let y = circuit.bus("y");
+        let z = circuit.bus("z");
+
+        let mut wires = circuit.wires();
+        int_to_wires(&mut wires, &x, 1);
+        int_to_wires(&mut wires, &y, 1);
+        circuit.evaluate(&mut wires)?;
+
+        assert_eq!(0b00, wires_to_int(&wires, &z));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bus_ordering() {
+        let circuit = build(&[
+            ("x10", "AND", "x02", "zed"),
+            ("x01", "OR", "x00", "z00"),
+        ]);
+
+        let x: Vec<_> = circuit.bus("x").iter().map(|w| circuit.name(*w)).collect();
+        let z: Vec<_> = circuit.bus("z").iter().map(|w| circuit.name(*w)).collect();
+
+        assert_eq!(x, vec!["x00", "x01", "x02", "x10"]);
+        assert_eq!(z, vec!["z00"]);
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let circuit = build(&[
+            ("x00", "AND", "b", "a"),
+            ("a", "OR", "x01", "b"),
+            ("b", "XOR", "x00", "z00"),
+        ]);
+
+        let err = circuit.evaluate(&mut circuit.wires()).unwrap_err();
+
+        let mut looped = err.0.clone();
+        looped.sort();
+        assert_eq!(looped, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_evaluate_in_reused_order() -> anyhow::Result<()> {
+        let circuit = build(&[
+            ("x00", "XOR", "y00", "z00"),
+            ("x00", "AND", "y00", "z01"),
+        ]);
+        let order = circuit.topological_order()?;
+        let (x, y, z) = (circuit.bus("x"), circuit.bus("y"), circuit.bus("z"));
+
+        for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let mut wires = circuit.wires();
+            int_to_wires(&mut wires, &x, a);
+            int_to_wires(&mut wires, &y, b);
+            circuit.evaluate_in(&order, &mut wires);
+
+            assert_eq!(wires_to_int(&wires, &z), a + b);
+        }
+        assert_eq!(circuit.lookup("z01"), Some(z[1]));
+        assert_eq!(circuit.lookup("q"), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+use num::{CheckedAdd, One, Zero};
+
+/// Number type for combinatorial counts. Fixed width integers report overflow through
+/// `checked_add`, `num::BigUint` never overflows.
+pub trait Count: Clone + Zero + One + CheckedAdd + fmt::Display { }
+
+impl<T> Count for T
+    where T: Clone + Zero + One + CheckedAdd + fmt::Display { }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountOverflow;
+
+impl fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "count does not fit the counting type")
+    }
+}
+
+impl std::error::Error for CountOverflow { }
+
+pub fn checked_sum<C: Count, I: IntoIterator<Item = C>>(counts: I) -> Result<C, CountOverflow> {
+    counts
+        .into_iter()
+        .try_fold(C::zero(), |total, c| total.checked_add(&c))
+        .ok_or(CountOverflow)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+    use super::*;
+
+    #[test]
+    fn test_checked_sum() {
+        assert_eq!(checked_sum([200u8, 55]), Ok(255));
+        assert_eq!(checked_sum([200u8, 56]), Err(CountOverflow));
+        assert_eq!(checked_sum([u64::MAX, 1].map(BigUint::from)), Ok(BigUint::from(u64::MAX) + 1u8));
+    }
+}
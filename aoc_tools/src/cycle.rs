@@ -0,0 +1,195 @@
+use crate::{Direction, Point};
+
+/// How a deterministic walk ends: it leaves after `steps` steps, or repeats
+/// the states from step `start` on every `length` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkEnd {
+    Exit { steps: usize },
+    Loop { start: usize, length: usize },
+}
+
+impl WalkEnd {
+    pub fn is_loop(&self) -> bool {
+        matches!(self, Self::Loop { .. })
+    }
+}
+
+/// Brent's cycle detection for walks whose states can only be compared. `step` gives
+/// the next state, or None once the walk leaves.
+pub fn detect_cycle<S, F>(start: S, mut step: F) -> WalkEnd
+    where S: Clone + PartialEq, F: FnMut(&S) -> Option<S>
+{
+    // the hare runs ahead, the tortoise teleports to it at every power of two
+    let mut power = 1;
+    let mut length = 1;
+    let mut steps = 1;
+    let mut tortoise = start.clone();
+    let Some(mut hare) = step(&start) else {
+        return WalkEnd::Exit { steps: 0 };
+    };
+
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+
+        let Some(next) = step(&hare) else {
+            return WalkEnd::Exit { steps };
+        };
+        hare = next;
+        length += 1;
+        steps += 1;
+    }
+
+    // with the hare `length` steps ahead, both meet where the loop begins
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..length {
+        hare = step(&hare).unwrap();
+    }
+
+    let mut loop_start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise).unwrap();
+        hare = step(&hare).unwrap();
+        loop_start += 1;
+    }
+
+    WalkEnd::Loop { start: loop_start, length }
+}
+
+/// State of the walk after `n` steps, skipping whole loops, or None if it left before.
+pub fn state_after<S, F>(start: S, mut step: F, n: usize) -> Option<S>
+    where S: Clone + PartialEq, F: FnMut(&S) -> Option<S>
+{
+    let n = match detect_cycle(start.clone(), &mut step) {
+        WalkEnd::Exit { steps } if n > steps => return None,
+        WalkEnd::Loop { start, length } if n > start => start + (n - start) % length,
+        _ => n,
+    };
+
+    (0..n).try_fold(start, |state, _| step(&state))
+}
+
+/// Step at which every (position, direction) of a grid walk was first reached. Finds loops
+/// without comparing whole histories, and can be reset cheaply for the next walk.
+#[derive(Debug, Clone)]
+pub struct DirectedVisits {
+    size: (usize, usize),
+    first_seen: Vec<u32>,
+    touched: Vec<usize>,
+}
+
+const UNSEEN: u32 = u32::MAX;
+
+impl DirectedVisits {
+    pub fn new(size: (usize, usize)) -> Self {
+        Self {
+            size,
+            first_seen: vec![UNSEEN; size.0 * size.1 * 4],
+            touched: Vec::new(),
+        }
+    }
+
+    /// Follows `step` from `start`, the visits are kept until the next `reset`.
+    pub fn walk<F>(&mut self, start: (Point, Direction), mut step: F) -> WalkEnd
+        where F: FnMut(Point, Direction) -> Option<(Point, Direction)>
+    {
+        let mut state = start;
+        let mut steps = 0;
+
+        loop {
+            let idx = self.index(state);
+            let seen = self.first_seen[idx];
+            if seen != UNSEEN {
+                return WalkEnd::Loop { start: seen as usize, length: steps - seen as usize };
+            }
+
+            self.first_seen[idx] = steps as u32;
+            self.touched.push(idx);
+
+            match step(state.0, state.1) {
+                Some(next) => state = next,
+                None => return WalkEnd::Exit { steps },
+            }
+            steps += 1;
+        }
+    }
+
+    pub fn first_seen(&self, pos: Point, dir: Direction) -> Option<usize> {
+        let seen = self.first_seen[self.index((pos, dir))];
+        (seen != UNSEEN).then_some(seen as usize)
+    }
+
+    /// Forgets all visits, only touching the entries of the previous walks.
+    pub fn reset(&mut self) {
+        for idx in self.touched.drain(..) {
+            self.first_seen[idx] = UNSEEN;
+        }
+    }
+
+    fn index(&self, (pos, dir): (Point, Direction)) -> usize {
+        (pos.y * self.size.0 + pos.x) * 4 + dir as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use crate::Rotation;
+    use super::*;
+
+    // 0 -> 1 -> ... -> tail, then around a loop of `length` states
+    fn rho(tail: usize, length: usize) -> impl Fn(&usize) -> Option<usize> {
+        move |s| Some(if *s + 1 == tail + length { tail } else { s + 1 })
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(0, 7)]
+    #[case(5, 1)]
+    #[case(13, 8)]
+    #[case(100, 33)]
+    fn test_detect_cycle(#[case] tail: usize, #[case] length: usize) {
+        assert_eq!(detect_cycle(0, rho(tail, length)), WalkEnd::Loop { start: tail, length });
+    }
+
+    #[test]
+    fn test_detect_exit() {
+        assert_eq!(detect_cycle(0, |s| (*s < 10).then_some(s + 1)), WalkEnd::Exit { steps: 10 });
+        assert_eq!(detect_cycle(0, |_: &usize| None), WalkEnd::Exit { steps: 0 });
+    }
+
+    #[test]
+    fn test_state_after() {
+        assert_eq!(state_after(0, rho(3, 4), 1_000_000_000), Some(3 + (1_000_000_000 - 3) % 4));
+        assert_eq!(state_after(0, rho(3, 4), 2), Some(2));
+        assert_eq!(state_after(0, |s| (*s < 10).then_some(s + 1), 10), Some(10));
+        assert_eq!(state_after(0, |s| (*s < 10).then_some(s + 1), 11), None);
+    }
+
+    #[test]
+    fn test_directed_visits() {
+        let size = (3, 3);
+        let mut visits = DirectedVisits::new(size);
+
+        // turning right at every edge circles the border forever
+        let circle = |pos: Point, dir: Direction| match pos.advance(dir, size) {
+            Some(next) => Some((next, dir)),
+            None => Some((pos, dir.turn(Rotation::Clockwise))),
+        };
+        let straight = |pos: Point, dir: Direction| pos.advance(dir, size).map(|next| (next, dir));
+
+        let start = (Point { x: 1, y: 2 }, Direction::Left);
+        assert_eq!(visits.walk(start, circle), WalkEnd::Loop { start: 0, length: 12 });
+        assert_eq!(visits.first_seen(Point { x: 0, y: 0 }, Direction::Right), Some(5));
+
+        visits.reset();
+        assert_eq!(visits.first_seen(Point { x: 0, y: 0 }, Direction::Right), None);
+        assert_eq!(visits.walk(start, straight), WalkEnd::Exit { steps: 1 });
+        assert_eq!(detect_cycle(start, |(p, d)| circle(*p, *d)), WalkEnd::Loop { start: 0, length: 12 });
+    }
+}
@@ -0,0 +1,110 @@
+/// Union-find over the elements 0..len, with path compression and union by size.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            sets: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Number of disjoint sets left.
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    /// Representative of the set containing `x`.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // point everything on the way straight at the root
+        let mut node = x;
+        while self.parent[node] != root {
+            node = std::mem::replace(&mut self.parent[node], root);
+        }
+
+        root
+    }
+
+    /// Merges the sets of `a` and `b`, false if they already were the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.sets -= 1;
+
+        true
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the set containing `x`.
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = DisjointSet::new(8);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert!(sets.union(5, 6));
+
+        assert!(sets.same(0, 3));
+        assert!(!sets.same(0, 5));
+        assert_eq!(sets.set_size(2), 4);
+        assert_eq!(sets.set_size(7), 1);
+        assert_eq!(sets.set_count(), 4);
+        assert_eq!(sets.len(), 8);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        // a chain deep enough to overflow a recursive find
+        let len = 1_000_000;
+        let mut sets = DisjointSet::new(len);
+        for i in 1..len {
+            sets.parent[i] = i - 1;
+        }
+
+        assert_eq!(sets.find(len - 1), 0);
+        assert!(sets.parent.iter().all(|p| *p == 0));
+    }
+}
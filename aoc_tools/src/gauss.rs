@@ -1,42 +1,99 @@
-use std::cmp::PartialEq;
-use std::ops::{DivAssign, Mul, SubAssign};
+use num::Signed;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solution<T> {
+    Unique(Vec<T>),
+    /// The solution with every free variable set to zero, and the indices of the free variables.
+    Infinite { particular: Vec<T>, free: Vec<usize> },
+    Inconsistent,
+}
 
-pub fn gauss_eliminate<const NROWS: usize, const NCOLS: usize, T> (m: &mut [[T; NCOLS]; NROWS]) -> bool
-    where T: Copy 
-           + PartialEq
-           + Default
-           + DivAssign
-           + Mul<Output=T>
-           + SubAssign
+/// Brings the matrix to reduced row echelon form, swapping in the row with the largest
+/// absolute value as pivot. Returns the pivot column of each leading row, its length is the rank.
+pub fn row_reduce<T>(m: &mut [Vec<T>]) -> Vec<usize>
+    where T: Clone + PartialOrd + Signed
 {
-    for i in 0..NROWS {
-        let d = m[i][i];
-        if d == T::default() {
-            return false;
+    let ncols = m.first().map_or(0, |row| row.len());
+    let mut pivots = Vec::new();
+
+    for col in 0..ncols {
+        let row = pivots.len();
+        if row == m.len() {
+            break;
         }
 
-        for c in i..NCOLS {
-            m[i][c] /= d;
+        let best = (row..m.len())
+            .filter(|r| !m[*r][col].is_zero())
+            .reduce(|a, b| if m[b][col].abs() > m[a][col].abs() { b } else { a });
+        let Some(best) = best else { continue };
+        m.swap(row, best);
+
+        let d = m[row][col].clone();
+        for v in m[row][col..].iter_mut() {
+            *v = v.clone() / d.clone();
         }
 
-        for j in (i + 1)..NROWS {
-            let mul = m[j][i];
-            for c in i..NCOLS {
-                m[j][c] -= m[i][c] * mul;
+        let pivot_row = m[row].clone();
+        for (other, target) in m.iter_mut().enumerate() {
+            let mul = target[col].clone();
+            if other == row || mul.is_zero() {
+                continue;
+            }
+            for (v, p) in target[col..].iter_mut().zip(&pivot_row[col..]) {
+                *v = v.clone() - p.clone() * mul.clone();
             }
         }
+
+        pivots.push(col);
     }
 
-    for i in (1..NROWS).rev() {
-        for j in 0..i {
-            let mul = m[j][i];
-            for c in i..NCOLS {
-                m[j][c] -= m[i][c] * mul;
-            }
+    pivots
+}
+
+/// Solves the system given as augmented matrix, the last column holding the right-hand side.
+pub fn solve_linear<T>(mut augmented: Vec<Vec<T>>) -> Solution<T>
+    where T: Clone + PartialOrd + Signed
+{
+    let nvars = augmented.first().map_or(0, |row| row.len().saturating_sub(1));
+    let pivots = row_reduce(&mut augmented);
+
+    if pivots.last() == Some(&nvars) {
+        // a row reads 0 = 1
+        return Solution::Inconsistent;
+    }
+
+    let mut values = vec![T::zero(); nvars];
+    for (row, col) in pivots.iter().enumerate() {
+        values[*col] = augmented[row][nvars].clone();
+    }
+
+    let free: Vec<usize> = (0..nvars).filter(|c| !pivots.contains(c)).collect();
+    if free.is_empty() {
+        Solution::Unique(values)
+    } else {
+        Solution::Infinite { particular: values, free }
+    }
+}
+
+/// Reduces an augmented matrix in place, true if the system has exactly one solution.
+/// Prefer `solve_linear`, which also tells apart inconsistent and underdetermined systems.
+///
+/// Breaking change: elements used to need `Copy + Default + DivAssign + Mul + SubAssign`,
+/// pivoting now needs `Clone + PartialOrd + Signed`, so unsigned element types no longer compile.
+pub fn gauss_eliminate<const NROWS: usize, const NCOLS: usize, T> (m: &mut [[T; NCOLS]; NROWS]) -> bool
+    where T: Clone + PartialOrd + Signed
+{
+    let mut rows: Vec<Vec<T>> = m.iter().map(|row| row.to_vec()).collect();
+    let pivots = row_reduce(&mut rows);
+
+    for (target, row) in m.iter_mut().zip(rows) {
+        for (t, v) in target.iter_mut().zip(row) {
+            *t = v;
         }
     }
 
-    true
+    // one pivot per variable, none in the right-hand side column
+    pivots.iter().copied().eq(0..NCOLS.saturating_sub(1))
 }
 
 
@@ -44,8 +101,12 @@ pub fn gauss_eliminate<const NROWS: usize, const NCOLS: usize, T> (m: &mut [[T;
 #[cfg(test)]
 mod tests {
     use num::Rational64;
+    use rstest::rstest;
     use super::*;
 
+    fn rationals(rows: &[Vec<i64>]) -> Vec<Vec<Rational64>> {
+        rows.iter().map(|row| row.iter().map(|v| Rational64::from(*v)).collect()).collect()
+    }
 
     #[test]
     fn test_rev_loop() {
@@ -77,4 +138,70 @@ mod tests {
             [0.into(), 1.into(), 40.into()],
         ])
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_needs_row_swap() {
+        let mut matrix: [[Rational64; 3]; 2] = [
+            [0.into(), 2.into(), 4.into()],
+            [3.into(), 1.into(), 5.into()],
+        ];
+
+        assert!(gauss_eliminate(&mut matrix));
+        assert_eq!(matrix, [
+            [1.into(), 0.into(), 1.into()],
+            [0.into(), 1.into(), 2.into()],
+        ]);
+    }
+
+    #[test]
+    fn test_eliminate_inconsistent() {
+        let mut inconsistent: [[Rational64; 3]; 2] = [
+            [1.into(), 1.into(), 3.into()],
+            [1.into(), 1.into(), 5.into()],
+        ];
+        let mut underdetermined: [[Rational64; 3]; 2] = [
+            [1.into(), 1.into(), 3.into()],
+            [2.into(), 2.into(), 6.into()],
+        ];
+
+        assert!(!gauss_eliminate(&mut inconsistent));
+        assert!(!gauss_eliminate(&mut underdetermined));
+    }
+
+    #[test]
+    fn test_eliminate_row_count() {
+        // x + y + z = 3, x - y = 0 leaves z free
+        let mut free_variable: [[Rational64; 4]; 2] = [
+            [1.into(), 1.into(), 1.into(), 3.into()],
+            [1.into(), (-1).into(), 0.into(), 0.into()],
+        ];
+        // x = 1, y = 2, x + y = 3: more equations than variables, still one solution
+        let mut overdetermined: [[Rational64; 3]; 3] = [
+            [1.into(), 0.into(), 1.into()],
+            [0.into(), 1.into(), 2.into()],
+            [1.into(), 1.into(), 3.into()],
+        ];
+
+        assert!(!gauss_eliminate(&mut free_variable));
+        assert!(gauss_eliminate(&mut overdetermined));
+        assert_eq!((overdetermined[0][2], overdetermined[1][2]), (1.into(), 2.into()));
+    }
+
+    #[rstest]
+    #[case(vec![vec![2, 1, -1, 8], vec![-3, -1, 2, -11], vec![-2, 1, 2, -3]], Solution::Unique(vec![2.into(), 3.into(), (-1).into()]))]
+    #[case(vec![vec![1, 2, 3], vec![2, 4, 6]], Solution::Infinite { particular: vec![3.into(), 0.into()], free: vec![1] })]
+    #[case(vec![vec![1, 2, 3], vec![2, 4, 7]], Solution::Inconsistent)]
+    #[case(vec![vec![0, 1, 2], vec![0, 0, 0]], Solution::Infinite { particular: vec![0.into(), 2.into()], free: vec![0] })]
+    #[case(vec![vec![1, 1], vec![1, 1], vec![2, 2]], Solution::Unique(vec![1.into()]))]
+    fn test_solve_linear(#[case] rows: Vec<Vec<i64>>, #[case] expected: Solution<Rational64>) {
+        assert_eq!(solve_linear(rationals(&rows)), expected);
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut m = rationals(&[vec![1, 2, 3], vec![2, 4, 6], vec![1, 0, 1]]);
+
+        assert_eq!(row_reduce(&mut m), vec![0, 1]);
+        assert_eq!(m[2], rationals(&[vec![0, 0, 0]])[0]);
+    }
+}
@@ -23,10 +23,31 @@ mod grid;
 pub use grid::Grid;
 
 mod gauss;
-pub use gauss::gauss_eliminate;
+pub use gauss::{gauss_eliminate, row_reduce, solve_linear, Solution};
 
 mod direction;
 pub use direction::{Direction, Rotation};
 
 mod point;
-pub use point::Point;
\ No newline at end of file
+pub use point::Point;
+
+mod circuit;
+pub use circuit::{Circuit, CircuitCycle, Node, NodeOp, int_to_wires, wires_to_int};
+
+mod count;
+pub use count::{Count, CountOverflow, checked_sum};
+
+mod cycle;
+pub use cycle::{detect_cycle, state_after, DirectedVisits, WalkEnd};
+
+mod numtheory;
+pub use numtheory::{crt, ext_gcd, lcm_of, mod_inverse, mul_mod};
+
+mod population;
+pub use population::Population;
+
+mod disjoint;
+pub use disjoint::DisjointSet;
+
+mod bfs;
+pub use bfs::{grid_bfs, GridDistances};
@@ -3,15 +3,147 @@ pub trait NumExt
         Self: Sized
 {
     fn clamped_add_signed(self, rhs: isize, limit: Self) -> Option<Self>;
+
+    /// 10^exp, if it fits the type.
+    fn checked_pow10(exp: u32) -> Option<Self>;
+
+    /// Number of decimal digits, zero has one.
+    fn digit_count(self) -> u32;
+
+    /// Splits off the lowest `at` decimal digits: 1234.split_digits(1) == (123, 4).
+    fn split_digits(self, at: u32) -> (Self, Self);
+
+    /// Decimal digits of `rhs` appended to `self`: 12.concat_digits(345) == Some(12345).
+    fn concat_digits(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_num_ext {
+    ($($t:ty),*) => {
+        $(
+            impl NumExt for $t {
+                fn clamped_add_signed(self, rhs: isize, limit: Self) -> Option<Self> {
+                    let distance = Self::try_from(rhs.unsigned_abs()).ok()?;
+                    let res = if rhs < 0 {
+                        self.checked_sub(distance)?
+                    } else {
+                        self.checked_add(distance)?
+                    };
+
+                    if res < limit {
+                        Some(res)
+                    } else {
+                        None
+                    }
+                }
+
+                fn checked_pow10(exp: u32) -> Option<Self> {
+                    Self::checked_pow(10, exp)
+                }
+
+                fn digit_count(self) -> u32 {
+                    self.checked_ilog10().unwrap_or(0) + 1
+                }
+
+                fn split_digits(self, at: u32) -> (Self, Self) {
+                    match Self::checked_pow10(at) {
+                        Some(divisor) => (self / divisor, self % divisor),
+                        None => (0, self),
+                    }
+                }
+
+                fn concat_digits(self, rhs: Self) -> Option<Self> {
+                    // a leading zero vanishes, even when the shift alone would overflow
+                    if self == 0 {
+                        return Some(rhs);
+                    }
+
+                    Self::checked_pow10(rhs.digit_count())?
+                        .checked_mul(self)?
+                        .checked_add(rhs)
+                }
+            }
+        )*
+    };
 }
 
-impl NumExt for usize {
-    fn clamped_add_signed(self, rhs: isize, limit: Self) -> Option<Self> {
-        let res = self.checked_add_signed(rhs)?;
-        if res < limit {
-            Some(res)
-        } else {
-            None
+impl_num_ext!(u8, u16, u32, u64, u128, usize);
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // values spread over all magnitudes, with extra weight around powers of ten
+    fn samples(count: usize) -> impl Iterator<Item = u128> {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        (0..count).map(move |_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+
+            let random = (seed as u128) << 64 | seed.rotate_left(29) as u128;
+            let magnitude = 10u128.pow((seed % 38) as u32);
+            match seed % 4 {
+                0 => magnitude,
+                1 => magnitude - 1,
+                _ => random % (magnitude * 10),
+            }
+        })
+    }
+
+    fn split_by_string(n: u128, at: u32) -> (u128, u128) {
+        let s = n.to_string();
+        match s.len().checked_sub(at as usize) {
+            _ if at == 0 => (n, 0),
+            Some(0) | None => (0, n),
+            Some(head) => (s[..head].parse().unwrap(), s[head..].parse().unwrap()),
         }
     }
+
+    macro_rules! check_against_strings {
+        ($name:ident, $t:ty) => {
+            #[test]
+            fn $name() {
+                for (a, b) in samples(2000).zip(samples(2001).skip(1)) {
+                    let (Some(a), Some(b)) = (<$t>::try_from(a).ok(), <$t>::try_from(b % 1_000_000).ok()) else {
+                        continue;
+                    };
+
+                    assert_eq!(a.digit_count() as usize, a.to_string().len(), "{}", a);
+
+                    for at in 0..=a.digit_count() + 1 {
+                        let (head, tail) = split_by_string(a as u128, at);
+                        assert_eq!(a.split_digits(at), (head as $t, tail as $t), "{} at {}", a, at);
+                    }
+
+                    let concat = format!("{}{}", a, b).parse::<$t>().ok();
+                    assert_eq!(a.concat_digits(b), concat, "{} {}", a, b);
+                }
+            }
+        };
+    }
+
+    check_against_strings!(test_digits_u8, u8);
+    check_against_strings!(test_digits_u16, u16);
+    check_against_strings!(test_digits_u32, u32);
+    check_against_strings!(test_digits_u64, u64);
+    check_against_strings!(test_digits_u128, u128);
+    check_against_strings!(test_digits_usize, usize);
+
+    #[test]
+    fn test_pow10_limits() {
+        assert_eq!(u8::checked_pow10(2), Some(100));
+        assert_eq!(u8::checked_pow10(3), None);
+        assert_eq!(u64::checked_pow10(19), Some(10_000_000_000_000_000_000));
+        assert_eq!(u64::checked_pow10(20), None);
+    }
+
+    #[test]
+    fn test_clamped_add_signed() {
+        assert_eq!(5usize.clamped_add_signed(-5, 10), Some(0));
+        assert_eq!(5usize.clamped_add_signed(-6, 10), None);
+        assert_eq!(5usize.clamped_add_signed(4, 10), Some(9));
+        assert_eq!(5usize.clamped_add_signed(5, 10), None);
+        assert_eq!(200u8.clamped_add_signed(300, 255), None);
+    }
 }
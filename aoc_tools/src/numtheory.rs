@@ -0,0 +1,154 @@
+use num::{CheckedMul, Integer, Signed};
+
+/// Returns (g, x, y) with a * x + b * y = g = gcd(a, b), g is never negative.
+pub fn ext_gcd<T: Integer + Signed + Copy>(a: T, b: T) -> (T, T, T) {
+    if b.is_zero() {
+        if a.is_negative() {
+            (-a, -T::one(), T::zero())
+        } else {
+            (a, T::one(), T::zero())
+        }
+    } else {
+        let (q, r) = a.div_mod_floor(&b);
+        let (g, x, y) = ext_gcd(b, r);
+        (g, y, x - q * y)
+    }
+}
+
+/// x in 0..m with a * x = 1 (mod m), if a and m are coprime.
+pub fn mod_inverse<T: Integer + Signed + Copy>(a: T, m: T) -> Option<T> {
+    let (g, x, _) = ext_gcd(a, m);
+    g.is_one().then(|| x.mod_floor(&m))
+}
+
+/// Combines congruences x = r (mod m) into a single (r, m), where m is the lcm of all moduli.
+/// The moduli don't need to be coprime. Conflicting congruences, moduli below one and an lcm
+/// that doesn't fit `T` give None.
+pub fn crt<T, I>(congruences: I) -> Option<(T, T)>
+    where T: Integer + Signed + CheckedMul + Copy, I: IntoIterator<Item = (T, T)>
+{
+    congruences
+        .into_iter()
+        .try_fold((T::zero(), T::one()), |(r1, m1), (r2, m2)| {
+            if m2 <= T::zero() {
+                return None;
+            }
+
+            let r2 = r2.mod_floor(&m2);
+            let (g, p, _) = ext_gcd(m1, m2);
+            let diff = r2 - r1;
+            if !diff.is_multiple_of(&g) {
+                return None;
+            }
+
+            // r1 + m1 * k hits r2 modulo m2 for k = p * diff / g
+            let m2g = m2 / g;
+            let k = mul_mod((diff / g).mod_floor(&m2g), p.mod_floor(&m2g), m2g);
+            let lcm = m1.checked_mul(&m2g)?;
+            // k < m2g, so m1 * k + r1 stays below the lcm
+            Some((r1 + m1 * k, lcm))
+        })
+}
+
+/// a * b mod m for a, b in 0..m, without the product ever exceeding 2 * m.
+pub fn mul_mod<T: Integer + Copy>(mut a: T, mut b: T, m: T) -> T {
+    let two = T::one() + T::one();
+    let add_mod = |x: T, y: T| if x >= m - y { x - (m - y) } else { x + y };
+
+    let mut product = T::zero();
+    while !b.is_zero() {
+        if b.is_odd() {
+            product = add_mod(product, a);
+        }
+        a = add_mod(a, a);
+        b = b / two;
+    }
+    product
+}
+
+pub fn lcm_of<T: Integer + Copy, I: IntoIterator<Item = T>>(values: I) -> T {
+    values
+        .into_iter()
+        .fold(T::one(), |acc, v| acc.lcm(&v))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest]
+    #[case(240, 46)]
+    #[case(-240, 46)]
+    #[case(17, 0)]
+    #[case(0, -5)]
+    #[case(0, 0)]
+    fn test_ext_gcd(#[case] a: i64, #[case] b: i64) {
+        let (g, x, y) = ext_gcd(a, b);
+
+        assert!(g >= 0);
+        assert_eq!(a * x + b * y, g);
+        if g != 0 {
+            assert_eq!((a % g, b % g), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(101i128, 103), Some(51));
+    }
+
+    #[rstest]
+    #[case(&[(2, 3), (3, 5), (2, 7)], Some((23, 105)))]
+    #[case(&[(3, 4), (5, 6)], Some((11, 12)))]
+    #[case(&[(1, 4), (2, 6)], None)]
+    #[case(&[(13, 101), (20, 103)], Some((4861, 10403)))]
+    #[case(&[], Some((0, 1)))]
+    fn test_crt(#[case] congruences: &[(i64, i64)], #[case] expected: Option<(i64, i64)>) {
+        let result = crt(congruences.iter().copied());
+
+        assert_eq!(result, expected);
+        if let Some((r, _)) = result {
+            assert!(congruences.iter().all(|(cr, m)| r % m == *cr));
+        }
+    }
+
+    #[test]
+    fn test_crt_near_limit() {
+        // both moduli just below sqrt(i64::MAX), the lcm only barely fits
+        let (m1, m2) = (3_037_000_493i64, 3_037_000_499i64);
+        let congruences = [(m1 - 1, m1), (m2 - 2, m2)];
+
+        let (r, m) = crt(congruences).unwrap();
+        let wide = crt(congruences.map(|(r, m)| (r as i128, m as i128))).unwrap();
+
+        assert_eq!((r as i128, m as i128), wide);
+        assert_eq!((r % m1, r % m2), (m1 - 1, m2 - 2));
+        assert_eq!(crt([(1, m1), (2, m2), (3, 5)]), None);
+    }
+
+    #[test]
+    fn test_crt_bad_moduli() {
+        assert_eq!(crt([(1, 3), (0, 0)]), None);
+        assert_eq!(crt([(1, -4)]), None);
+        assert_eq!(crt([(-1, 4)]), Some((3, 4)));
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        let m = i64::MAX - 24;
+        assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+        assert_eq!(mul_mod(123_456_789u64, 987_654_321, 1_000_000_007), 123_456_789 * 987_654_321 % 1_000_000_007);
+    }
+
+    #[test]
+    fn test_lcm_of() {
+        assert_eq!(lcm_of([4u64, 6, 10]), 60);
+        assert_eq!(lcm_of([101usize, 103]), 10403);
+        assert_eq!(lcm_of(Vec::<u32>::new()), 1);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{checked_sum, Count, CountOverflow};
+
+/// Multiset of items where every generation replaces each item with its successors.
+/// Only a count per distinct item is kept, so the cost depends on how many different
+/// items there are, not on how large the population grows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Population<T: Hash + Eq, C = u64> {
+    counts: HashMap<T, C>,
+}
+
+impl<T: Hash + Eq + Clone, C: Count> Population<T, C> {
+    pub fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+        }
+    }
+
+    /// One of each item, repeated items are counted.
+    pub fn from_items<I: IntoIterator<Item = T>>(items: I) -> Result<Self, CountOverflow> {
+        let mut population = Self::new();
+        for item in items {
+            population.add(item, C::one())?;
+        }
+        Ok(population)
+    }
+
+    pub fn add(&mut self, item: T, count: C) -> Result<(), CountOverflow> {
+        let entry = self.counts.entry(item).or_insert_with(C::zero);
+        *entry = entry.checked_add(&count).ok_or(CountOverflow)?;
+        Ok(())
+    }
+
+    /// Next generation, `rule` gives the successors of a single item.
+    pub fn evolve<F, I>(&self, mut rule: F) -> Result<Self, CountOverflow>
+        where F: FnMut(&T) -> I, I: IntoIterator<Item = T>
+    {
+        self.try_evolve(|item| Ok(rule(item)))
+    }
+
+    pub fn evolve_n<F, I>(&self, generations: usize, mut rule: F) -> Result<Self, CountOverflow>
+        where F: FnMut(&T) -> I, I: IntoIterator<Item = T>
+    {
+        self.try_evolve_n(generations, |item| Ok(rule(item)))
+    }
+
+    /// Like `evolve`, for rules that can fail on some item.
+    pub fn try_evolve<F, I, E>(&self, mut rule: F) -> Result<Self, E>
+        where F: FnMut(&T) -> Result<I, E>, I: IntoIterator<Item = T>, E: From<CountOverflow>
+    {
+        let mut next = Self::new();
+
+        for (item, count) in self.counts.iter() {
+            for successor in rule(item)? {
+                next.add(successor, count.clone())?;
+            }
+        }
+
+        Ok(next)
+    }
+
+    pub fn try_evolve_n<F, I, E>(&self, generations: usize, mut rule: F) -> Result<Self, E>
+        where F: FnMut(&T) -> Result<I, E>, I: IntoIterator<Item = T>, E: From<CountOverflow>
+    {
+        let mut population = self.clone();
+        for _ in 0..generations {
+            population = population.try_evolve(&mut rule)?;
+        }
+        Ok(population)
+    }
+
+    pub fn count(&self, item: &T) -> C {
+        self.counts.get(item).cloned().unwrap_or_else(C::zero)
+    }
+
+    /// Number of items, counting duplicates.
+    pub fn total(&self) -> Result<C, CountOverflow> {
+        checked_sum(self.counts.values().cloned())
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &C)> {
+        self.counts.iter()
+    }
+}
+
+impl<T: Hash + Eq + Clone, C: Count> Default for Population<T, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+    use rstest::rstest;
+    use super::*;
+
+    // every item expanded on its own, only usable for a few generations
+    fn naive<F, I>(items: &[u32], generations: usize, rule: F) -> Vec<u32>
+        where F: Fn(&u32) -> I, I: IntoIterator<Item = u32>
+    {
+        let mut items = items.to_vec();
+        for _ in 0..generations {
+            items = items.iter().flat_map(&rule).collect();
+        }
+        items
+    }
+
+    fn collatz(n: &u32) -> Vec<u32> {
+        match n {
+            1 => vec![1, 2],
+            n if n % 2 == 0 => vec![n / 2],
+            n => vec![3 * n + 1, n + 1],
+        }
+    }
+
+    fn doubling(b: &bool) -> [bool; 2] {
+        [!b, *b]
+    }
+
+    #[rstest]
+    #[case(&[1], 5)]
+    #[case(&[7, 7, 12], 10)]
+    #[case(&[27, 3, 1], 14)]
+    fn test_matches_naive(#[case] items: &[u32], #[case] generations: usize) -> Result<(), CountOverflow> {
+        let expected = naive(items, generations, collatz);
+        let population: Population<u32, usize> = Population::from_items(items.iter().copied())?;
+
+        let evolved = population.evolve_n(generations, collatz)?;
+
+        assert_eq!(evolved.total()?, expected.len());
+        for (item, count) in evolved.iter() {
+            assert_eq!(*count, expected.iter().filter(|e| *e == item).count());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_many_generations() -> Result<(), CountOverflow> {
+        // doubles every generation, but only two items ever exist
+        let population: Population<bool> = Population::from_items([false])?;
+
+        let evolved = population.evolve_n(40, doubling)?;
+
+        assert_eq!(evolved.distinct(), 2);
+        assert_eq!(evolved.total()?, 1 << 40);
+        assert_eq!(evolved.count(&true), 1 << 39);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<(), CountOverflow> {
+        let narrow: Population<bool, u64> = Population::from_items([false])?;
+        let wide: Population<bool, u128> = Population::from_items([false])?;
+        let big: Population<bool, BigUint> = Population::from_items([false])?;
+
+        assert_eq!(narrow.evolve_n(63, doubling)?.total(), Ok(1 << 63));
+        assert_eq!(narrow.evolve_n(64, doubling)?.total(), Err(CountOverflow));
+        assert_eq!(narrow.evolve_n(65, doubling).err(), Some(CountOverflow));
+        assert_eq!(wide.evolve_n(64, doubling)?.total(), Ok(1 << 64));
+        assert_eq!(big.evolve_n(1000, doubling)?.total(), Ok(BigUint::from(1u8) << 1000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_failing_rule() -> anyhow::Result<()> {
+        let population: Population<u8> = Population::from_items([100, 20])?;
+        let double = |n: &u8| n.checked_mul(2).map(|d| [d]).ok_or(anyhow::anyhow!("{} is too large", n));
+
+        assert_eq!(population.try_evolve_n(1, double)?.count(&200), 1);
+        assert_eq!(population.try_evolve_n(2, double).unwrap_err().to_string(), "200 is too large");
+        Ok(())
+    }
+
+    #[test]
+    fn test_extinction() -> Result<(), CountOverflow> {
+        let population: Population<u32> = Population::from_items([3, 5])?;
+
+        let evolved = population.evolve_n(3, |n: &u32| (*n > 1).then_some(n - 1))?;
+
+        assert_eq!(evolved.total()?, 1);
+        assert_eq!(evolved.count(&2), 1);
+        Ok(())
+    }
+}
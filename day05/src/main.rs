@@ -1,7 +1,11 @@
 use aoc_tools::{IterMoreTools, InvalidInput, ResultExt};
 use itertools::Itertools;
-use std::cmp::Ordering;
 use std::collections::{HashSet, HashMap};
+use std::env;
+
+mod order;
+
+use order::PageGraph;
 
 type RuleMap = HashMap<u32, HashSet<u32>>;
 type Updates = Vec<Vec<u32>>;
@@ -11,6 +15,11 @@ fn main() -> anyhow::Result<()> {
 
     let (updates, rule_map) = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--check") {
+        check_updates(&updates, &rule_map);
+        return Ok(());
+    }
+
     let result1 = calculate_p1(&updates, &rule_map)?;
     println!("Result p1: {}", result1);
 
@@ -85,9 +94,11 @@ fn parse_update<S: AsRef<str>>(dim: S) -> Result<Vec<u32>, InvalidInput>
 
 fn calculate_p1(updates: &Updates, rule_map: &RuleMap) -> anyhow::Result<u32> {
 
+    let graph = PageGraph::from_rules(rule_map);
+
     let safe_updates: Vec<_> = updates
     .iter()
-    .filter(|u|is_update_safe(u, rule_map))
+    .filter(|u|is_update_safe(u, &graph))
     .collect();
 
     let result1 = safe_updates
@@ -100,11 +111,13 @@ fn calculate_p1(updates: &Updates, rule_map: &RuleMap) -> anyhow::Result<u32> {
 
 fn calculate_p2(updates: &Updates, rule_map: &RuleMap) -> anyhow::Result<u32> {
 
+    let graph = PageGraph::from_rules(rule_map);
+
     let fixed_updates: Vec<_> = updates
         .iter()
-        .filter(|u|!is_update_safe(u, &rule_map))
-        .map(|u| fix_unsafe_update(u, &rule_map))
-        .collect();
+        .filter(|u|!is_update_safe(u, &graph))
+        .map(|u| fix_unsafe_update(u, &graph))
+        .try_collect()?;
 
     let result2 = fixed_updates
         .iter()
@@ -115,10 +128,8 @@ fn calculate_p2(updates: &Updates, rule_map: &RuleMap) -> anyhow::Result<u32> {
 }
 
 
-fn is_update_safe(update: &Vec<u32>, rules: &RuleMap) -> bool {
-    update
-        .iter()
-        .is_sorted_by(|a, b| cmp_by_rules(a, b, rules) != Ordering::Greater)
+fn is_update_safe(update: &[u32], rules: &PageGraph) -> bool {
+    rules.is_ordered(update)
 }
 
 fn extract_middle_page(update: &[u32]) -> anyhow::Result<u32> {
@@ -129,25 +140,37 @@ fn extract_middle_page(update: &[u32]) -> anyhow::Result<u32> {
 }
 
 
-fn fix_unsafe_update(bad_one: &Vec<u32>, rules: &RuleMap) -> Vec<u32> {
-    let mut fixed = bad_one.clone();
-
-    fixed.sort_by(|a, b| cmp_by_rules(a, b, rules));
+fn fix_unsafe_update(bad_one: &[u32], rules: &PageGraph) -> anyhow::Result<Vec<u32>> {
+    let sorted = rules.restrict(bad_one)?.topo_sort()?;
 
-    fixed
+    Ok(sorted.pages)
 }
 
-fn cmp_by_rules(a: &u32, b: &u32, page_rules: &RuleMap) -> Ordering {
-    if let Some(rule) = page_rules.get(a) {
-        if rule.contains(b) {
-            return Ordering::Less
+/// Prints for every update whether it is in order, and if not, whether the rules
+/// allow only one fixed order or contradict each other.
+fn check_updates(updates: &Updates, rule_map: &RuleMap) {
+    let graph = PageGraph::from_rules(rule_map);
+
+    for update in updates {
+        let pages = update.iter().join(",");
+        if is_update_safe(update, &graph) {
+            println!("{}: in order", pages);
+            continue;
         }
-    }
 
-    if a == b {
-        Ordering::Equal
-    } else {
-        Ordering::Greater
+        let restricted = match graph.restrict(update) {
+            Ok(restricted) => restricted,
+            Err(repeated) => {
+                println!("{}: {}", pages, repeated);
+                continue;
+            },
+        };
+
+        match restricted.topo_sort() {
+            Ok(sorted) if sorted.unique => println!("{}: fixed to {}", pages, sorted.pages.iter().join(",")),
+            Ok(sorted) => println!("{}: fixed to {}, not the only order", pages, sorted.pages.iter().join(",")),
+            Err(cycle) => println!("{}: {}", pages, cycle),
+        }
     }
 }
 
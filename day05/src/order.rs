@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use aoc_tools::InvalidInput;
+use itertools::Itertools;
+
+use crate::RuleMap;
+
+/// Page rules as a directed graph, an edge a -> b means a has to be printed before b.
+#[derive(Debug, Clone)]
+pub struct PageGraph {
+    pages: Vec<u32>,
+    successors: HashMap<u32, Vec<u32>>,
+}
+
+/// Pages in an order that respects every rule. `unique` tells whether it is the only one,
+/// which is the case when the rules chain all pages together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopoOrder {
+    pub pages: Vec<u32>,
+    pub unique: bool,
+}
+
+/// Pages whose rules contradict each other, each one has to come before the next
+/// and the last one before the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCycle {
+    pub pages: Vec<u32>,
+}
+
+impl fmt::Display for RuleCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rules form a cycle: {}", self.pages.iter().chain(self.pages.first()).join(" -> "))
+    }
+}
+
+impl std::error::Error for RuleCycle { }
+
+impl PageGraph {
+    pub fn from_rules(rules: &RuleMap) -> Self {
+        let pages = rules
+            .iter()
+            .flat_map(|(before, afters)| afters.iter().chain([before]))
+            .copied()
+            .unique()
+            .sorted()
+            .collect();
+
+        let successors = rules
+            .iter()
+            .map(|(before, afters)| (*before, afters.iter().copied().sorted().collect()))
+            .collect();
+
+        Self { pages, successors }
+    }
+
+    /// Only the given pages and the rules between them, keeping the order of `pages`.
+    /// A page given twice can't be placed once, that is an error.
+    pub fn restrict(&self, pages: &[u32]) -> Result<Self, InvalidInput> {
+        let mut wanted: HashSet<u32> = HashSet::with_capacity(pages.len());
+        if let Some(page) = pages.iter().find(|p| !wanted.insert(**p)) {
+            return Err(InvalidInput(format!("page {} appears more than once in {}", page, pages.iter().join(","))));
+        }
+
+        let successors = pages
+            .iter()
+            .map(|page| (*page, self.successors_of(*page).filter(|s| wanted.contains(s)).collect()))
+            .collect();
+
+        Ok(Self {
+            pages: pages.to_vec(),
+            successors,
+        })
+    }
+
+    pub fn successors_of(&self, page: u32) -> impl Iterator<Item = u32> + '_ {
+        self.successors.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Whether `order` breaks none of the rules between its pages.
+    pub fn is_ordered(&self, order: &[u32]) -> bool {
+        let position: HashMap<u32, usize> = order.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+
+        order.iter().all(|page| {
+            self.successors_of(*page)
+                .all(|s| position.get(&s).is_none_or(|&after| position[page] < after))
+        })
+    }
+
+    /// Kahn's algorithm. Pages without a rule between them keep the order they were given in.
+    pub fn topo_sort(&self) -> Result<TopoOrder, RuleCycle> {
+        let mut in_degree: HashMap<u32, usize> = self.pages.iter().map(|p| (*p, 0)).collect();
+        for page in &self.pages {
+            for s in self.successors_of(*page) {
+                *in_degree.get_mut(&s).unwrap() += 1;
+            }
+        }
+
+        let mut ready: VecDeque<u32> = self.pages.iter().copied().filter(|p| in_degree[p] == 0).collect();
+        let mut sorted = Vec::with_capacity(self.pages.len());
+        let mut unique = true;
+
+        while let Some(page) = ready.pop_front() {
+            // a second page that could go next means both orders are allowed
+            unique &= ready.is_empty();
+            sorted.push(page);
+
+            for s in self.successors_of(page) {
+                let degree = in_degree.get_mut(&s).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push_back(s);
+                }
+            }
+        }
+
+        if sorted.len() < self.pages.len() {
+            let placed: HashSet<u32> = sorted.into_iter().collect();
+            return Err(self.find_cycle(&placed));
+        }
+
+        Ok(TopoOrder { pages: sorted, unique })
+    }
+
+    // every page Kahn couldn't place still has an unplaced predecessor, so walking
+    // backwards through them has to run into a page a second time
+    fn find_cycle(&self, placed: &HashSet<u32>) -> RuleCycle {
+        let mut predecessor: HashMap<u32, u32> = HashMap::new();
+        for page in self.pages.iter().filter(|p| !placed.contains(p)) {
+            for s in self.successors_of(*page).filter(|s| !placed.contains(s)) {
+                predecessor.insert(s, *page);
+            }
+        }
+
+        let mut walked = Vec::new();
+        let mut page = *self.pages.iter().find(|p| !placed.contains(p)).unwrap();
+        while !walked.contains(&page) {
+            walked.push(page);
+            page = predecessor[&page];
+        }
+
+        let start = walked.iter().position(|p| *p == page).unwrap();
+        let mut pages = walked.split_off(start);
+        pages.reverse();
+        RuleCycle { pages }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(rules: &[(u32, u32)]) -> PageGraph {
+        let mut rule_map = RuleMap::new();
+        for (before, after) in rules {
+            rule_map.entry(*before).or_default().insert(*after);
+        }
+        PageGraph::from_rules(&rule_map)
+    }
+
+    #[test]
+    fn test_topo_sort() {
+        let rules = graph(&[(1, 2), (2, 3), (1, 3), (4, 3)]);
+
+        assert_eq!(rules.restrict(&[3, 2, 1]).unwrap().topo_sort(), Ok(TopoOrder { pages: vec![1, 2, 3], unique: true }));
+        assert_eq!(rules.restrict(&[3, 4, 1]).unwrap().topo_sort(), Ok(TopoOrder { pages: vec![4, 1, 3], unique: false }));
+        assert_eq!(rules.restrict(&[5]).unwrap().topo_sort(), Ok(TopoOrder { pages: vec![5], unique: true }));
+        assert!(rules.is_ordered(&[4, 1, 2, 3]));
+        assert!(!rules.is_ordered(&[1, 3, 2]));
+    }
+
+    #[test]
+    fn test_repeated_page() {
+        let rules = graph(&[(1, 2), (2, 3)]);
+
+        let err = rules.restrict(&[3, 1, 2, 1, 5]).unwrap_err();
+
+        assert_eq!(err, InvalidInput("page 1 appears more than once in 3,1,2,1,5".to_owned()));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let rules = graph(&[(1, 2), (2, 3), (3, 4), (4, 2), (4, 5), (6, 1)]);
+
+        let cycle = rules.topo_sort().unwrap_err();
+
+        assert_eq!(cycle.pages.len(), 3);
+        for (before, after) in cycle.pages.iter().circular_tuple_windows() {
+            assert!(rules.successors_of(*before).contains(after));
+        }
+        // without page 3 the loop is broken
+        assert!(rules.restrict(&[1, 2, 4, 5, 6]).unwrap().topo_sort().is_ok());
+        assert_eq!(rules.restrict(&[4, 2, 3]).unwrap().topo_sort().unwrap_err().to_string(), "rules form a cycle: 2 -> 3 -> 4 -> 2");
+    }
+}
@@ -0,0 +1,132 @@
+use aoc_tools::{Direction, DirectedVisits, Grid, Point, Rotation};
+
+/// For every cell and direction, the last free cell before the next obstacle,
+/// so a walk can go from turn to turn instead of cell by cell.
+pub struct JumpTable {
+    size: (usize, usize),
+    stops: Vec<Option<Point>>,
+}
+
+impl JumpTable {
+    pub fn new(grid: &Grid<char>) -> Self {
+        let size = grid.size();
+        let mut table = Self {
+            size,
+            stops: vec![None; size.0 * size.1 * 4],
+        };
+
+        for dir in Direction::all() {
+            // cells closest to the edge we walk towards come first, so the cell ahead is always done
+            for pos in cells_towards(dir, size) {
+                let stop = match pos.advance(dir, size) {
+                    None => None,
+                    Some(ahead) if grid[ahead] == '#' => Some(pos),
+                    Some(ahead) => table.stops[table.index(ahead, dir)],
+                };
+                let idx = table.index(pos, dir);
+                table.stops[idx] = stop;
+            }
+        }
+
+        table
+    }
+
+    /// Where the walk from `pos` towards `dir` stops, also stopping in front of `extra`.
+    /// None means the walk leaves the grid.
+    pub fn stop(&self, pos: Point, dir: Direction, extra: Option<Point>) -> Option<Point> {
+        let stop = self.stops[self.index(pos, dir)];
+
+        let Some(extra_dist) = extra.and_then(|e| distance_ahead(pos, e, dir)) else {
+            return stop;
+        };
+
+        match stop {
+            Some(s) if distance_ahead(pos, s, dir).unwrap_or(0) < extra_dist => Some(s),
+            _ => Some(pos.advance_with_distance(dir, self.size, extra_dist - 1).unwrap()),
+        }
+    }
+
+    fn index(&self, pos: Point, dir: Direction) -> usize {
+        (pos.y * self.size.0 + pos.x) * 4 + dir as usize
+    }
+}
+
+/// Whether the guard walks in circles once `extra` is blocked, following the table from turn to turn.
+pub fn loops_with_obstacle(table: &JumpTable, start: Point, extra: Point, visits: &mut DirectedVisits) -> bool {
+    visits.reset();
+
+    visits
+        .walk((start, Direction::Up), |pos, dir| {
+            let stop = table.stop(pos, dir, Some(extra))?;
+            Some((stop, dir.turn(Rotation::Clockwise)))
+        })
+        .is_loop()
+}
+
+fn cells_towards(dir: Direction, (width, height): (usize, usize)) -> impl Iterator<Item = Point> {
+    (0..width * height).map(move |i| {
+        let (major, minor) = (i / width, i % width);
+        let (x, y) = (i / height, i % height);
+        match dir {
+            Direction::Up => Point { x: minor, y: major },
+            Direction::Down => Point { x: minor, y: height - 1 - major },
+            Direction::Left => Point { x, y },
+            Direction::Right => Point { x: width - 1 - x, y },
+        }
+    })
+}
+
+/// Number of steps from `from` to `to` when walking towards `dir`, if `to` lies ahead.
+fn distance_ahead(from: Point, to: Point, dir: Direction) -> Option<usize> {
+    let (along, across) = match dir {
+        Direction::Up => (from.y.checked_sub(to.y), from.x == to.x),
+        Direction::Down => (to.y.checked_sub(from.y), from.x == to.x),
+        Direction::Left => (from.x.checked_sub(to.x), from.y == to.y),
+        Direction::Right => (to.x.checked_sub(from.x), from.y == to.y),
+    };
+
+    along.filter(|d| *d > 0 && across)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_from(rows: &[&str]) -> Grid<char> {
+        let mut grid = Grid::new('.', rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                grid[Point { x, y }] = c;
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_stops() {
+        let grid = grid_from(&[
+            "..#..",
+            ".....",
+            "#....",
+            ".....",
+        ]);
+        let table = JumpTable::new(&grid);
+        let p = |x, y| Point { x, y };
+
+        assert_eq!(table.stop(p(2, 3), Direction::Up, None), Some(p(2, 1)));
+        assert_eq!(table.stop(p(2, 1), Direction::Up, None), Some(p(2, 1)));
+        assert_eq!(table.stop(p(4, 2), Direction::Left, None), Some(p(1, 2)));
+        assert_eq!(table.stop(p(1, 1), Direction::Down, None), None);
+
+        assert_eq!(table.stop(p(2, 3), Direction::Up, Some(p(2, 2))), Some(p(2, 3)));
+        assert_eq!(table.stop(p(4, 2), Direction::Left, Some(p(3, 2))), Some(p(4, 2)));
+        assert_eq!(table.stop(p(4, 2), Direction::Left, Some(p(2, 2))), Some(p(3, 2)));
+        assert_eq!(table.stop(p(1, 0), Direction::Down, Some(p(1, 3))), Some(p(1, 2)));
+
+        // behind the walker, past the obstacle or off the line: no effect
+        assert_eq!(table.stop(p(2, 3), Direction::Up, Some(p(2, 0))), Some(p(2, 1)));
+        assert_eq!(table.stop(p(4, 2), Direction::Left, Some(p(4, 2))), Some(p(1, 2)));
+        assert_eq!(table.stop(p(1, 1), Direction::Down, Some(p(2, 3))), None);
+    }
+}
@@ -1,18 +1,41 @@
-use aoc_tools::{Direction, Grid, InvalidInput, Point, Rotation};
-use ndarray::{Array3, ShapeBuilder};
+use aoc_tools::{Direction, DirectedVisits, Grid, InvalidInput, Point, Rotation};
 use std::collections::HashSet;
+use std::env;
+use std::time::Instant;
 use rayon::prelude::*;
 
+mod jump;
+mod route;
+
+use jump::{loops_with_obstacle, JumpTable};
+use route::{render_route, walk_route};
+
 type ParsedInput = (Grid<char>, Point);
 
 fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    match env::args().nth(2).as_deref() {
+        Some("--bench") => {
+            benchmark_p2(&parsed);
+            return Ok(());
+        },
+        Some("--route") => {
+            print_route(&parsed);
+            return Ok(());
+        },
+        Some("--loops") => {
+            print_loops(&parsed);
+            return Ok(());
+        },
+        _ => {},
+    }
+
     let result1 = calculate_p1(&parsed);
     println!("Result p1: {}", result1);
 
-    let result2 = calculate_p2(&parsed);
+    let result2 = calculate_p2_v2(&parsed);
     println!("Result p2: {}", result2);
 
     Ok(())
@@ -38,74 +61,105 @@ fn calculate_p1(input: &ParsedInput) -> usize {
 }
 
 fn walk_unobstructed(grid: &Grid<char>, pos: &Point) -> HashSet<Point> {
-    let mut visited: HashSet<Point> = HashSet::new();
+    walk_route(grid, *pos, None)
+        .states
+        .iter()
+        .map(|guard| guard.pos)
+        .collect()
+}
 
-    let mut guard = GuardState::new(*pos);
+fn calculate_p2(input: &ParsedInput) -> usize {
+    let (grid, pos) = input;
 
-    visited.insert(*pos);
+    let mut base_path = walk_unobstructed(&grid, pos);
 
-    while let Some(new_pos) = guard.step(grid.size()) {
+    base_path.remove(pos);
 
-        let cell_val = grid[new_pos.pos];
+    let obstacles = base_path
+        .par_iter()
+        .map_init(
+            || DirectedVisits::new(grid.size()),
+            |visits, ob| walk_detect_loop(grid, *pos, *ob, visits))
+        .sum();
 
-        if cell_val == '#' {
-            guard = guard.turn();
-            continue;
-        }
+    obstacles
+}
 
-        guard = new_pos;
+fn walk_detect_loop(grid: &Grid<char>, pos: Point, ob: Point, visits: &mut DirectedVisits) -> usize {
+    visits.reset();
 
-        visited.insert(guard.pos);
+    let guard = GuardState::new(pos);
+    let end = visits.walk((guard.pos, guard.dir), |pos, dir| {
+        let guard = GuardState { pos, dir };
+        let new_pos = guard.step(grid.size())?;
 
-    }
+        if grid[new_pos.pos] == '#' || new_pos.pos == ob {
+            let turned = guard.turn();
+            Some((turned.pos, turned.dir))
+        } else {
+            Some((new_pos.pos, new_pos.dir))
+        }
+    });
 
-    visited
+    if end.is_loop() { 1 } else { 0 }
 }
 
-fn calculate_p2(input: &ParsedInput) -> usize {
+fn calculate_p2_v2(input: &ParsedInput) -> usize {
     let (grid, pos) = input;
+    let table = JumpTable::new(grid);
 
-    let mut base_path = walk_unobstructed(&grid, pos);
+    let mut base_path = walk_unobstructed(grid, pos);
 
     base_path.remove(pos);
 
-    let obstacles = base_path
+    base_path
         .par_iter()
-        .map(|ob|walk_detect_loop(&grid, *pos, *ob))
-        .sum();
-
-    obstacles
+        .map_init(
+            || DirectedVisits::new(grid.size()),
+            |visits, ob| loops_with_obstacle(&table, *pos, *ob, visits))
+        .filter(|looped| *looped)
+        .count()
 }
 
-fn walk_detect_loop(grid: &Grid<char>, pos: Point, ob: Point) -> usize {
-
-    let (width, height) = grid.size();
-    let mut visited: Array3<bool> = Array3::from_elem((width, height, 4).f(), false);
+fn benchmark_p2(input: &ParsedInput) {
+    const ROUNDS: u32 = 20;
 
-    let mut guard = GuardState::new(pos);
+    let time = |name: &str, f: &dyn Fn(&ParsedInput) -> usize| {
+        let started = Instant::now();
+        let mut result = 0;
+        for _ in 0..ROUNDS {
+            result = f(input);
+        }
+        println!("{:<10} {:>6} {:>10.3?}", name, result, started.elapsed() / ROUNDS);
+    };
 
-    *visited.get_mut(guard.as_index()).unwrap() = true;
+    time("stepwise", &calculate_p2);
+    time("jumps", &calculate_p2_v2);
+}
 
-    while let Some(new_pos) = guard.step(grid.size()) {
+fn print_route(input: &ParsedInput) {
+    let (grid, pos) = input;
 
-        let cell_val = grid[new_pos.pos];
+    println!("{}", render_route(grid, &walk_route(grid, *pos, None), None));
+}
 
-        if cell_val == '#' || (new_pos.pos == ob) {
-            guard = guard.turn();
-            continue;
-        }
+fn print_loops(input: &ParsedInput) {
+    let (grid, pos) = input;
 
-        guard = new_pos;
+    let mut candidates: Vec<Point> = walk_unobstructed(grid, pos).into_iter().collect();
+    candidates.retain(|ob| ob != pos);
+    candidates.sort_by_key(|ob| (ob.y, ob.x));
 
-        if *visited.get(guard.as_index()).unwrap() {
-            return 1;
+    for ob in candidates {
+        let route = walk_route(grid, *pos, Some(ob));
+        if route.looped {
+            println!("obstacle at {},{}", ob.x, ob.y);
+            println!("{}\n", render_route(grid, &route, Some(ob)));
         }
-        *visited.get_mut(guard.as_index()).unwrap() = true;
     }
-
-    0
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct GuardState {
     pos: Point,
     dir: Direction,
@@ -132,9 +186,11 @@ impl GuardState {
             dir: self.dir,
         })
     }
+}
 
-    pub fn as_index(&self) -> (usize, usize, usize) {
-        (self.pos.x, self.pos.y, self.dir as usize)
+impl From<GuardState> for (Point, Direction) {
+    fn from(guard: GuardState) -> Self {
+        (guard.pos, guard.dir)
     }
 }
 
@@ -170,6 +226,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_detect_loop() -> anyhow::Result<()> {
+        let ((grid, pos), _, _) = load_sample("sample.txt")?;
+        let mut visits = DirectedVisits::new(grid.size());
+
+        assert_eq!(walk_detect_loop(&grid, pos, Point { x: 3, y: 6 }, &mut visits), 1);
+        assert_eq!(walk_detect_loop(&grid, pos, Point { x: 0, y: 0 }, &mut visits), 0);
+        assert_eq!(walk_detect_loop(&grid, pos, Point { x: 7, y: 9 }, &mut visits), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_p2_v2() -> anyhow::Result<()> {
+        for filename in ["sample.txt", "input.txt"] {
+            let (parsed, _, expected) = load_sample(filename)?;
+
+            assert_eq!(calculate_p2_v2(&parsed), calculate_p2(&parsed));
+            assert_eq!(expected, Some(calculate_p2_v2(&parsed) as u64));
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_sample_p2() -> anyhow::Result<()> {
         let (parsed, _, expected) = load_sample("sample.txt")?;
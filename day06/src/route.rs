@@ -0,0 +1,114 @@
+use aoc_tools::{Direction, DirectedVisits, Grid, Point};
+
+use crate::GuardState;
+
+/// Every state of the guard in walking order, turns included, and whether the walk
+/// ends in a loop instead of leaving the grid.
+pub struct Route {
+    pub states: Vec<GuardState>,
+    pub looped: bool,
+}
+
+/// Walks the guard from `start`, treating `obstacle` like an extra `#`.
+pub fn walk_route(grid: &Grid<char>, start: Point, obstacle: Option<Point>) -> Route {
+    let mut states = Vec::new();
+    let mut visits = DirectedVisits::new(grid.size());
+
+    let guard = GuardState::new(start);
+    let end = visits.walk((guard.pos, guard.dir), |pos, dir| {
+        let guard = GuardState { pos, dir };
+        states.push(guard);
+        let ahead = guard.step(grid.size())?;
+
+        if grid[ahead.pos] == '#' || Some(ahead.pos) == obstacle {
+            Some(guard.turn().into())
+        } else {
+            Some(ahead.into())
+        }
+    });
+
+    Route {
+        states,
+        looped: end.is_loop(),
+    }
+}
+
+/// Draws the route like the puzzle text: `|` and `-` for the way walked, `+` where
+/// the guard turned or crossed its own path, `O` for the extra obstacle.
+pub fn render_route(grid: &Grid<char>, route: &Route, obstacle: Option<Point>) -> String {
+    let mut canvas = grid.map(|c| c);
+
+    for (i, guard) in route.states.iter().enumerate() {
+        let turned = i > 0 && route.states[i - 1].pos == guard.pos;
+        let mark = match guard.dir {
+            Direction::Up | Direction::Down => '|',
+            Direction::Left | Direction::Right => '-',
+        };
+
+        let cell = &mut canvas[guard.pos];
+        *cell = match *cell {
+            '^' => '^',
+            '.' if !turned => mark,
+            c if c == mark && !turned => mark,
+            _ => '+',
+        };
+    }
+
+    if let Some(ob) = obstacle {
+        canvas[ob] = 'O';
+    }
+
+    (0..canvas.height())
+        .map(|y| (0..canvas.width()).map(|x| canvas[Point { x, y }]).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use aoc_tools::TestSamples;
+    use super::*;
+
+    #[test]
+    fn test_render_loop() -> anyhow::Result<()> {
+        let samples = TestSamples::try_new()?;
+        let (input, _, _) = samples.get_sample("sample.txt")?;
+        let (grid, start) = crate::parse_input(input)?;
+        let obstacle = Point { x: 3, y: 6 };
+
+        let route = walk_route(&grid, start, Some(obstacle));
+
+        assert!(route.looped);
+        assert_eq!(render_route(&grid, &route, Some(obstacle)), [
+            "....#.....",
+            "....+---+#",
+            "....|...|.",
+            "..#.|...|.",
+            "....|..#|.",
+            "....|...|.",
+            ".#.O^---+.",
+            "........#.",
+            "#.........",
+            "......#...",
+        ].join("\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_route_order() -> anyhow::Result<()> {
+        let samples = TestSamples::try_new()?;
+        let (input, _, _) = samples.get_sample("sample.txt")?;
+        let (grid, start) = crate::parse_input(input)?;
+
+        let route = walk_route(&grid, start, None);
+
+        assert!(!route.looped);
+        assert_eq!(route.states[0], GuardState::new(start));
+        // five steps up, then a turn on the spot
+        assert_eq!(route.states[5].pos, Point { x: 4, y: 1 });
+        assert_eq!(route.states[6], GuardState { pos: Point { x: 4, y: 1 }, dir: Direction::Right });
+        assert_eq!(route.states.last().map(|g| g.pos), Some(Point { x: 7, y: 9 }));
+        Ok(())
+    }
+}
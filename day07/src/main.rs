@@ -1,5 +1,10 @@
-use aoc_tools::{IterMoreTools, ResultExt};
+use aoc_tools::{InvalidInput, IterMoreTools, ResultExt};
 use rayon::prelude::*;
+use std::env;
+
+mod operator;
+
+use operator::{find_operator, format_solution, solve, Add, Concat, Multiply, Operator};
 
 type ParsedInput = Vec<(u64, Vec<u64>)>;
 
@@ -7,10 +12,20 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--explain") {
+        let names = env::args().nth(3).unwrap_or("add,mul,concat".to_owned());
+        let ops: Vec<_> = names
+            .split(',')
+            .map(|name| find_operator(name).ok_or_else(|| InvalidInput(name.to_owned())))
+            .collect::<Result<_, _>>()?;
+        print_solutions(&parsed, &ops);
+        return Ok(());
+    }
+
     let result1 = calculate_p1(&parsed)?;
     println!("Result p1: {}", result1);
 
-    let result2 = calculate_p2_v2(&parsed)?;
+    let result2 = calculate_p2(&parsed)?;
     println!("Result p2: {}", result2);
 
     Ok(())
@@ -37,84 +52,29 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
 }
 
 fn calculate_p1(input: &ParsedInput) -> anyhow::Result<u64> {
-    Ok(input
-        .into_iter()
-        .filter(|(expected, args)| calc_exp_value_1(*expected, args))
-        .map(|(expected, _)| expected)
-        .sum())
+    Ok(sum_solvable(input, &[&Add, &Multiply]))
 }
 
-fn calc_exp_value_1(expected: u64, args: &Vec<u64>) -> bool {
-
-    (0..(1 << args.len()-1))
-        .into_iter()
-        .any(|pattern| {
-            let (_, result) = args
-                .into_iter()
-                .cloned()
-                .enumerate()
-                .reduce(|(_, total), (idx, item)| {
-                    if pattern & (1 << (idx - 1)) == 0 {
-                        (idx, total + item)
-                    } else {
-                        (idx, total * item)
-                    }
-                })
-                .unwrap();
-
-            result == expected
-        })
+fn calculate_p2(input: &ParsedInput) -> anyhow::Result<u64> {
+    Ok(sum_solvable(input, &[&Add, &Multiply, &Concat]))
 }
 
-fn calculate_p2_v2(input: &ParsedInput) -> anyhow::Result<u64> {
-    Ok(input
+fn sum_solvable(input: &ParsedInput, ops: &[&dyn Operator]) -> u64 {
+    input
         .into_par_iter()
-        .filter(|(expected, args)| calc_exp_value_2_v2(*expected, args))
+        .filter(|(expected, args)| solve(*expected, args, ops).is_some())
         .map(|(expected, _)| expected)
-        .sum())
+        .sum()
 }
 
-fn calc_exp_value_2_v2(expected: u64, args: &Vec<u64>) -> bool {
-
-    let mut args = args.into_iter().cloned();
-
-    if let Some(value) = args.next() {
-        let m = apply_op_and_check(expected, value, args);
-        m
-    } else {
-        false
-    }
-}
-
-fn apply_op_and_check<I>(expected: u64, calculated: u64, mut args: I) -> bool
-    where I: Iterator<Item = u64> + Clone
-{
-    if calculated > expected {
-        return false;
-    }
-
-    if let Some(arg) = args.next() {
-        let add_res = calculated + arg;
-        if apply_op_and_check(expected, add_res, args.clone()) {
-            return true;
+fn print_solutions(input: &ParsedInput, ops: &[&dyn Operator]) {
+    for (expected, args) in input {
+        if let Some(found) = solve(*expected, args, ops) {
+            println!("{}", format_solution(*expected, args, &found));
         }
-
-        let mul_res = calculated * arg;
-        if apply_op_and_check(expected, mul_res, args.clone()) {
-            return true;
-        }
-
-        let concat_res = concat_numbers(calculated, arg);
-        return apply_op_and_check(expected, concat_res, args.clone());
-    } else {
-        return expected == calculated;
     }
 }
 
-fn concat_numbers(a: u64, b: u64) -> u64 {
-    format!("{}{}", a, b).parse().unwrap()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,10 +98,10 @@ mod tests {
     }
 
     #[test]
-    fn test_sample_p2_v2() -> anyhow::Result<()> {
+    fn test_sample_p2() -> anyhow::Result<()> {
         let (parsed, _, expected) = load_sample("sample.txt")?;
 
-        let result2 = calculate_p2_v2(&parsed)?;
+        let result2 = calculate_p2(&parsed)?;
 
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
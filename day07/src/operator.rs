@@ -0,0 +1,239 @@
+use aoc_tools::NumExt;
+
+/// What an operator allows for the left operand, given the result and the right operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inverse {
+    None,
+    Exactly(u64),
+    Any,
+}
+
+pub trait Operator: Sync {
+    fn name(&self) -> &'static str;
+
+    fn symbol(&self) -> &'static str;
+
+    /// `lhs op rhs`, None when the result does not fit or is undefined.
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64>;
+
+    /// Left operands for which `lhs op rhs == result`.
+    fn undo(&self, result: u64, rhs: u64) -> Inverse;
+}
+
+pub struct Add;
+
+pub struct Multiply;
+
+/// Appends the digits of the right operand: 12 || 345 = 12345.
+pub struct Concat;
+
+/// Only defined while the result stays non-negative.
+pub struct Subtract;
+
+pub struct Xor;
+
+pub fn all_operators() -> Vec<&'static dyn Operator> {
+    vec![&Add, &Multiply, &Concat, &Subtract, &Xor]
+}
+
+pub fn find_operator(name: &str) -> Option<&'static dyn Operator> {
+    all_operators()
+        .into_iter()
+        .find(|op| op.name() == name || op.symbol() == name)
+}
+
+impl Operator for Add {
+    fn name(&self) -> &'static str {
+        "add"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_add(rhs)
+    }
+
+    fn undo(&self, result: u64, rhs: u64) -> Inverse {
+        result.checked_sub(rhs).map_or(Inverse::None, Inverse::Exactly)
+    }
+}
+
+impl Operator for Multiply {
+    fn name(&self) -> &'static str {
+        "mul"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_mul(rhs)
+    }
+
+    fn undo(&self, result: u64, rhs: u64) -> Inverse {
+        match (result, rhs) {
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::None,
+            _ if result.is_multiple_of(rhs) => Inverse::Exactly(result / rhs),
+            _ => Inverse::None,
+        }
+    }
+}
+
+impl Operator for Concat {
+    fn name(&self) -> &'static str {
+        "concat"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.concat_digits(rhs)
+    }
+
+    fn undo(&self, result: u64, rhs: u64) -> Inverse {
+        match result.split_digits(rhs.digit_count()) {
+            (prefix, suffix) if suffix == rhs => Inverse::Exactly(prefix),
+            _ => Inverse::None,
+        }
+    }
+}
+
+impl Operator for Subtract {
+    fn name(&self) -> &'static str {
+        "sub"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        lhs.checked_sub(rhs)
+    }
+
+    fn undo(&self, result: u64, rhs: u64) -> Inverse {
+        result.checked_add(rhs).map_or(Inverse::None, Inverse::Exactly)
+    }
+}
+
+impl Operator for Xor {
+    fn name(&self) -> &'static str {
+        "xor"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+        Some(lhs ^ rhs)
+    }
+
+    fn undo(&self, result: u64, rhs: u64) -> Inverse {
+        Inverse::Exactly(result ^ rhs)
+    }
+}
+
+/// Operators that make `args`, evaluated left to right, equal `target`. Searches from the
+/// last operand backwards, so every operator can rule itself out by its inverse.
+pub fn solve<'a>(target: u64, args: &[u64], ops: &[&'a dyn Operator]) -> Option<Vec<&'a dyn Operator>> {
+    let (&last, rest) = args.split_last()?;
+    if rest.is_empty() {
+        return (last == target).then(Vec::new);
+    }
+
+    ops.iter().find_map(|op| {
+        let mut found = match op.undo(target, last) {
+            Inverse::None => None,
+            Inverse::Exactly(lhs) => solve(lhs, rest, ops),
+            Inverse::Any => evaluate_any(rest, ops),
+        }?;
+        found.push(*op);
+        Some(found)
+    })
+}
+
+// some operators that let `args` evaluate at all, when the value itself doesn't matter
+fn evaluate_any<'a>(args: &[u64], ops: &[&'a dyn Operator]) -> Option<Vec<&'a dyn Operator>> {
+    fn extend<'a>(value: u64, args: &[u64], ops: &[&'a dyn Operator]) -> Option<Vec<&'a dyn Operator>> {
+        let Some((&next, rest)) = args.split_first() else {
+            return Some(Vec::new());
+        };
+
+        ops.iter().find_map(|op| {
+            let mut found = extend(op.apply(value, next)?, rest, ops)?;
+            found.insert(0, *op);
+            Some(found)
+        })
+    }
+
+    let (&first, rest) = args.split_first()?;
+    extend(first, rest, ops)
+}
+
+/// The equation written out with the operators found by `solve`: "3267 = 81 * 40 + 27".
+pub fn format_solution(target: u64, args: &[u64], ops: &[&dyn Operator]) -> String {
+    let mut text = format!("{} = {}", target, args[0]);
+    for (op, arg) in ops.iter().zip(&args[1..]) {
+        text.push_str(&format!(" {} {}", op.symbol(), arg));
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    fn evaluate(args: &[u64], ops: &[&dyn Operator]) -> Option<u64> {
+        ops.iter()
+            .zip(&args[1..])
+            .try_fold(args[0], |value, (op, arg)| op.apply(value, *arg))
+    }
+
+    #[rstest]
+    #[case(190, &[10, 19], "add,mul", Some("190 = 10 * 19"))]
+    #[case(3267, &[81, 40, 27], "add,mul", Some("3267 = 81 * 40 + 27"))]
+    #[case(156, &[15, 6], "add,mul", None)]
+    #[case(156, &[15, 6], "add,mul,concat", Some("156 = 15 || 6"))]
+    #[case(7290, &[6, 8, 6, 15], "add,mul,concat", Some("7290 = 6 * 8 || 6 * 15"))]
+    #[case(3, &[10, 3, 6], "sub,xor", Some("3 = 10 ^ 3 - 6"))]
+    #[case(0, &[5, 7, 0], "add,mul", Some("0 = 5 + 7 * 0"))]
+    #[case(42, &[42], "add", Some("42 = 42"))]
+    fn test_solve(#[case] target: u64, #[case] args: &[u64], #[case] names: &str, #[case] expected: Option<&str>) {
+        let ops: Vec<_> = names.split(',').map(|n| find_operator(n).unwrap()).collect();
+
+        let found = solve(target, args, &ops);
+
+        assert_eq!(found.as_deref().map(|f| format_solution(target, args, f)).as_deref(), expected);
+        if let Some(f) = found {
+            assert_eq!(evaluate(args, &f), Some(target));
+        }
+    }
+
+    #[test]
+    fn test_undo_inverts_apply() {
+        let values = [0, 1, 2, 7, 10, 99, 100, 1234, u64::MAX / 3, u64::MAX];
+
+        for op in all_operators() {
+            for lhs in values {
+                for rhs in values {
+                    let Some(result) = op.apply(lhs, rhs) else { continue };
+
+                    match op.undo(result, rhs) {
+                        Inverse::Exactly(undone) => assert_eq!(op.apply(undone, rhs), Some(result), "{} {} {}", lhs, op.name(), rhs),
+                        Inverse::Any => assert_eq!((result, rhs), (0, 0)),
+                        Inverse::None => panic!("{} {} {} can't be undone", lhs, op.name(), rhs),
+                    }
+                }
+            }
+        }
+    }
+}
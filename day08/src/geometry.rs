@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use aoc_tools::{ext_gcd, NumExt, Point};
+use itertools::Itertools;
+
+/// Which multiples of the distance between two antennas count as antinodes.
+/// Harmonic 0 is the antenna itself, 1 the classic antinode at twice the distance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Harmonics {
+    Only(usize),
+    Set(Vec<usize>),
+    /// Every grid point exactly in line with the two antennas. Unlike the other two this
+    /// steps by the distance divided by its gcd, so it also takes the points in between
+    /// and is a superset of every `Set`.
+    All,
+}
+
+/// `p` moved by `k` times (dx, dy), if it stays on the grid.
+pub fn offset(p: Point, (dx, dy): (isize, isize), k: isize, (width, height): (usize, usize)) -> Option<Point> {
+    let x = p.x.clamped_add_signed(dx.checked_mul(k)?, width)?;
+    let y = p.y.clamped_add_signed(dy.checked_mul(k)?, height)?;
+
+    Some(Point { x, y })
+}
+
+/// All grid points on the line through `a` and `b`, in order along the line. The step is
+/// reduced by the gcd, so points between the two are included too.
+pub fn line_through(a: Point, b: Point, size: (usize, usize)) -> Vec<Point> {
+    let (dx, dy) = (b.x as isize - a.x as isize, b.y as isize - a.y as isize);
+    let (g, _, _) = ext_gcd(dx, dy);
+    if g == 0 {
+        return vec![a];
+    }
+    let step = (dx / g, dy / g);
+
+    let backwards = (1..).map_while(|k| offset(a, step, -k, size));
+    let forwards = (0..).map_while(|k| offset(a, step, k, size));
+
+    let mut points: Vec<Point> = backwards.collect();
+    points.reverse();
+    points.extend(forwards);
+    points
+}
+
+/// Antinodes of every pair of antennas within each group, for the given harmonics.
+pub fn antinodes<'a, I>(groups: I, size: (usize, usize), harmonics: &Harmonics) -> HashSet<Point>
+    where I: IntoIterator<Item = &'a [Point]>
+{
+    let mut found = HashSet::new();
+
+    for group in groups {
+        for (a, b) in group.iter().tuple_combinations() {
+            let diff = (b.x as isize - a.x as isize, b.y as isize - a.y as isize);
+            let multiples = match harmonics {
+                Harmonics::Only(k) => std::slice::from_ref(k),
+                Harmonics::Set(ks) => ks.as_slice(),
+                Harmonics::All => {
+                    found.extend(line_through(*a, *b, size));
+                    continue;
+                }
+            };
+
+            for k in multiples {
+                let k = *k as isize;
+                found.extend(offset(*a, diff, -k, size));
+                found.extend(offset(*b, diff, k, size));
+            }
+        }
+    }
+
+    found
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_line_through() {
+        assert_eq!(line_through(p(2, 2), p(4, 6), (10, 10)), vec![p(1, 0), p(2, 2), p(3, 4), p(4, 6), p(5, 8)]);
+        assert_eq!(line_through(p(6, 3), p(2, 3), (8, 5)), (0..8).rev().map(|x| p(x, 3)).collect::<Vec<_>>());
+        assert_eq!(line_through(p(0, 0), p(3, 3), (3, 3)), vec![p(0, 0), p(1, 1), p(2, 2)]);
+        assert_eq!(line_through(p(1, 1), p(1, 1), (3, 3)), vec![p(1, 1)]);
+    }
+
+    #[test]
+    fn test_harmonics() {
+        let group = [p(3, 4), p(5, 5)];
+        let size = (10, 10);
+        let count = |harmonics| antinodes([&group[..]], size, &harmonics).len();
+
+        assert_eq!(antinodes([&group[..]], size, &Harmonics::Only(1)), HashSet::from([p(1, 3), p(7, 6)]));
+        assert_eq!(antinodes([&group[..]], size, &Harmonics::Only(0)), HashSet::from(group));
+        assert_eq!(count(Harmonics::Set(vec![0, 1, 2, 3])), 5);
+        assert_eq!(count(Harmonics::All), 5);
+
+        // an even distance leaves a point halfway that only All picks up
+        let spaced = [p(2, 2), p(4, 4)];
+        let all = antinodes([&spaced[..]], (7, 7), &Harmonics::All);
+        let set = antinodes([&spaced[..]], (7, 7), &Harmonics::Set(vec![0, 1, 2]));
+        assert_eq!(all.len(), 7);
+        assert_eq!(set, HashSet::from([p(2, 2), p(4, 4), p(0, 0), p(6, 6)]));
+        assert!(all.is_superset(&set));
+    }
+}
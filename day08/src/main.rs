@@ -1,6 +1,10 @@
-use aoc_tools::{NumExt, Point};
+use aoc_tools::{Grid, InvalidInput, Point};
 use itertools::Itertools;
-use std::collections::HashSet;
+use std::env;
+
+mod geometry;
+
+use geometry::{antinodes, Harmonics};
 
 type ParsedInput = (Vec<(char, Vec<Point>)>, (usize, usize));
 
@@ -8,6 +12,17 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--harmonics") {
+        let list = env::args().nth(3).unwrap_or("1".to_owned());
+        let harmonics: Vec<usize> = list
+            .split(',')
+            .map(str::parse)
+            .try_collect()
+            .map_err(|_| InvalidInput(list.clone()))?;
+        println!("Antinodes: {}", count_antinodes(&parsed, &Harmonics::Set(harmonics)));
+        return Ok(());
+    }
+
     let result1 = calculate_p1(&parsed);
     println!("Result p1: {}", result1);
 
@@ -20,92 +35,40 @@ fn main() -> anyhow::Result<()> {
 fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
     let grid = input.read_grid()?;
 
-    let mut locations: Vec<(char, Point)> = Vec::new();
-
-    for (cell, pos) in grid.enumerate() {
-        if *cell != '.' {
-            locations.push((*cell, pos));
-        }
-    }
-
-    println!("{:?}", locations);
+    Ok((group_antennas(&grid), grid.size()))
+}
 
-    let grouping = locations
-        .into_iter()
+/// Antenna positions grouped by frequency, every cell other than '.' is an antenna.
+fn group_antennas(grid: &Grid<char>) -> Vec<(char, Vec<Point>)> {
+    let grouping = grid
+        .enumerate()
+        .filter(|(cell, _)| **cell != '.')
+        .map(|(cell, pos)| (*cell, pos))
         .sorted_by_key(|(k, _)| *k)
         .chunk_by(|(k, _)| *k);
 
-    let loc_groups: Vec<_> = grouping
+    grouping
         .into_iter()
         .map(|(key, val)| (key,
             val
                 .map(|(_, v)| v)
                 .collect::<Vec<Point>>()
         ))
-        .collect();
-
-    Ok((loc_groups, grid.size()))
+        .collect()
 }
 
 fn calculate_p1(input: &ParsedInput) -> usize {
-    let (loc_groups, (width, height)) = input;
-
-    let mut antinodes: HashSet<Point> = HashSet::new();
-
-    for (_, loc) in loc_groups.into_iter() {
-        let pairs = loc.into_iter().tuple_combinations::<(_, _)>();
-        for (a, b) in pairs {
-
-            let diffx = b.x as isize - a.x as isize;
-            let diffy = b.y as isize - a.y as isize;
-
-            if let Some(anta) = calc_ant(a, -diffx, -diffy, *width, *height) {
-                antinodes.insert(anta);
-            }
-
-            if let Some(antb) = calc_ant(b, diffx, diffy, *width, *height) {
-                antinodes.insert(antb);
-            }
-        }
-    }
-
-    antinodes.len()
+    count_antinodes(input, &Harmonics::Only(1))
 }
 
-fn calc_ant(t: &Point, dx: isize, dy: isize, width: usize, height: usize) -> Option<Point> {
-    let antx = t.x.clamped_add_signed(dx, width)?;
-    let anty = t.y.clamped_add_signed(dy, height)?;
-
-    Some((antx, anty).into())
+fn calculate_p2(input: &ParsedInput) -> usize {
+    count_antinodes(input, &Harmonics::All)
 }
 
+fn count_antinodes(input: &ParsedInput, harmonics: &Harmonics) -> usize {
+    let (loc_groups, size) = input;
 
-fn calculate_p2(input: &ParsedInput) -> usize {
-    let (loc_groups, (width, height)) = input;
-
-    let mut antinodes: HashSet<Point> = HashSet::new();
-
-    for (_, loc) in loc_groups.into_iter() {
-        let pairs = loc.into_iter().tuple_combinations::<(_, _)>();
-        for (a, b) in pairs {
-            let diffx = b.x as isize - a.x as isize;
-            let diffy = b.y as isize - a.y as isize;
-
-            let mut multiplier = 0;
-            while let Some(anta) = calc_ant(a, -diffx * multiplier, -diffy * multiplier, *width, *height) {
-                antinodes.insert(anta);
-                multiplier += 1;
-            }
-
-            multiplier = 0;
-            while let Some(antb) = calc_ant(b, diffx * multiplier, diffy * multiplier, *width, *height) {
-                antinodes.insert(antb);
-                multiplier += 1;
-            }
-        }
-    }
-
-    antinodes.len()
+    antinodes(loc_groups.iter().map(|(_, loc)| loc.as_slice()), *size, harmonics).len()
 }
 
 #[cfg(test)]
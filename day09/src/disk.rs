@@ -0,0 +1,347 @@
+use core::fmt;
+
+use aoc_tools::InvalidInput;
+use itertools::Itertools;
+
+use crate::InputItem;
+
+/// Largest size a single digit of the dense format can describe.
+const MAX_DIGIT: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extent {
+    pub file_id: usize,
+    pub start: usize,
+    pub size: usize,
+}
+
+/// Disk layout as a list of file extents ordered by position, anything between them is free.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disk {
+    files: Vec<Extent>,
+    size: usize,
+}
+
+/// Dense digit map of a disk. The format numbers files by order of appearance, so the real
+/// id of every non-empty file is kept alongside, in disk order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseMap {
+    pub digits: String,
+    pub file_ids: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragmentation {
+    pub extents: usize,
+    pub fragmented_files: usize,
+    pub holes: usize,
+    pub largest_free: usize,
+    pub used_end: usize,
+}
+
+/// Reads alternating file and free sizes, e.g. `2333133121414131402`. Trailing whitespace is ignored.
+pub fn parse_disk_map(line: &str) -> Result<Vec<InputItem>, InvalidInput> {
+    line
+        .trim_end()
+        .chars()
+        .enumerate()
+        .map(|(pos, c)| {
+            let size = c.to_digit(10).ok_or_else(|| InvalidInput(format!("{} at position {}", c.escape_debug(), pos)))?;
+            Ok(InputItem {
+                file_id: if pos % 2 == 0 { Some(pos / 2) } else { None },
+                size: size as usize,
+            })
+        })
+        .collect()
+}
+
+impl Extent {
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+
+    fn checksum(&self) -> u64 {
+        // sum of (pos * file_id) over start..end, without walking the blocks
+        let (start, size) = (self.start as u64, self.size as u64);
+        self.file_id as u64 * (size * start + size * size.saturating_sub(1) / 2)
+    }
+}
+
+impl Disk {
+    pub fn from_items(items: &[InputItem]) -> Self {
+        let mut files = Vec::new();
+        let mut pos = 0;
+
+        for item in items {
+            if let Some(file_id) = item.file_id {
+                if item.size > 0 {
+                    files.push(Extent { file_id, start: pos, size: item.size });
+                }
+            }
+            pos += item.size;
+        }
+
+        Self {
+            files,
+            size: pos,
+        }
+    }
+
+    /// Free spans as (start, size), in disk order.
+    pub fn free_spans(&self) -> Vec<(usize, usize)> {
+        let mut spans = Vec::new();
+        let mut pos = 0;
+
+        for file in self.files.iter() {
+            if file.start > pos {
+                spans.push((pos, file.start - pos));
+            }
+            pos = file.end();
+        }
+
+        if self.size > pos {
+            spans.push((pos, self.size - pos));
+        }
+
+        spans
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.files
+            .iter()
+            .map(Extent::checksum)
+            .sum()
+    }
+
+    pub fn files(&self) -> &[Extent] {
+        &self.files
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        let spans = self.free_spans();
+        let used_end = self.files.last().map_or(0, Extent::end);
+
+        let file_ids = self.files.iter().map(|f| f.file_id).sorted().collect_vec();
+        let fragmented_files = file_ids
+            .iter()
+            .dedup_with_count()
+            .filter(|(count, _)| *count > 1)
+            .count();
+
+        Fragmentation {
+            extents: self.files.len(),
+            fragmented_files,
+            holes: spans.iter().filter(|(start, _)| *start < used_end).count(),
+            largest_free: spans.iter().map(|(_, size)| *size).max().unwrap_or(0),
+            used_end,
+        }
+    }
+
+    /// Writes the layout in the dense format. Runs longer than a digit are split into
+    /// several entries, empty files also pad free space at the very start of the disk.
+    pub fn to_dense(&self) -> DenseMap {
+        let mut digits = String::new();
+        let mut file_ids = Vec::new();
+        let mut pos = 0;
+
+        for file in self.files.iter() {
+            push_free(&mut digits, file.start - pos);
+            push_digit(&mut digits, file.size);
+            file_ids.extend(std::iter::repeat_n(file.file_id, file.size.div_ceil(MAX_DIGIT)));
+            pos = file.end();
+        }
+
+        if self.size > pos {
+            push_free(&mut digits, self.size - pos);
+        }
+
+        DenseMap {
+            digits,
+            file_ids,
+        }
+    }
+
+    /// Same layout seen from the other end of the disk.
+    pub fn mirrored(&self) -> Self {
+        let files = self.files
+            .iter()
+            .map(|f| Extent { file_id: f.file_id, start: self.size - f.end(), size: f.size })
+            .collect();
+
+        self.with_files(files)
+    }
+
+    /// Layout with the given extents, neighbouring pieces of the same file are merged.
+    pub fn with_files(&self, mut files: Vec<Extent>) -> Self {
+        files.sort_by_key(|f| f.start);
+
+        let mut merged: Vec<Extent> = Vec::with_capacity(files.len());
+        for file in files {
+            match merged.last_mut() {
+                Some(last) if last.file_id == file.file_id && last.end() == file.start => {
+                    last.size += file.size;
+                },
+                _ => merged.push(file),
+            }
+        }
+
+        Self {
+            files: merged,
+            size: self.size,
+        }
+    }
+}
+
+impl DenseMap {
+    pub fn to_disk(&self) -> Result<Disk, InvalidInput> {
+        let mut items = parse_disk_map(&self.digits)?;
+        let mut ids = self.file_ids.iter();
+
+        for item in items.iter_mut().filter(|i| i.file_id.is_some() && i.size > 0) {
+            item.file_id = Some(*ids.next().ok_or(InvalidInput("more files than ids".to_owned()))?);
+        }
+
+        if ids.next().is_some() {
+            return InvalidInput::err("more ids than files");
+        }
+
+        let disk = Disk::from_items(&items);
+        Ok(disk.with_files(disk.files.clone()))
+    }
+}
+
+// file sizes go at even positions of the map and free sizes at odd ones
+fn push_digit(digits: &mut String, mut size: usize) {
+    loop {
+        let digit = size.min(MAX_DIGIT);
+        digits.push(char::from_digit(digit as u32, 10).unwrap());
+        size -= digit;
+
+        if size == 0 {
+            break;
+        }
+        digits.push('0');
+    }
+}
+
+fn push_free(digits: &mut String, size: usize) {
+    if digits.is_empty() {
+        if size == 0 {
+            return;
+        }
+        digits.push('0');
+    }
+
+    push_digit(digits, size);
+}
+
+impl fmt::Display for Disk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pos = 0;
+
+        for file in self.files.iter() {
+            write!(f, "{}", ".".repeat(file.start - pos))?;
+
+            // ids past 9 continue with letters and wrap around after 'z'
+            let symbol = char::from_digit((file.file_id % 36) as u32, 36).unwrap();
+            write!(f, "{}", symbol.to_string().repeat(file.size))?;
+            pos = file.end();
+        }
+
+        write!(f, "{}", ".".repeat(self.size - pos))
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    pub fn disk_from_digits(digits: &[usize]) -> Disk {
+        let items: Vec<_> = digits
+            .iter()
+            .enumerate()
+            .map(|(i, d)| InputItem {
+                file_id: if i % 2 == 0 { Some(i / 2) } else { None },
+                size: *d,
+            })
+            .collect();
+
+        Disk::from_items(&items)
+    }
+
+    #[test]
+    fn test_extent_checksum() {
+        let extent = Extent { file_id: 3, start: 4, size: 3 };
+
+        assert_eq!(extent.checksum(), 3 * 4 + 3 * 5 + 3 * 6);
+    }
+
+    #[test]
+    fn test_render() {
+        let disk = disk_from_digits(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(disk.to_string(), "0..111....22222");
+        assert_eq!(disk.mirrored().to_string(), "22222....111..0");
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let disk = disk_from_digits(&[1, 2, 3, 4, 5]);
+        let split = disk.with_files(vec![
+            Extent { file_id: 2, start: 0, size: 2 },
+            Extent { file_id: 1, start: 2, size: 3 },
+            Extent { file_id: 2, start: 5, size: 1 },
+            Extent { file_id: 2, start: 6, size: 1 },
+        ]);
+
+        assert_eq!(split.to_string(), "2211122........");
+        assert_eq!(split.fragmentation(), Fragmentation {
+            extents: 3,
+            fragmented_files: 1,
+            holes: 0,
+            largest_free: 8,
+            used_end: 7,
+        });
+        assert_eq!(disk.fragmentation().holes, 2);
+    }
+
+    #[rstest]
+    #[case("2333133121414131402\n", 19)]
+    #[case("12345\r\n", 5)]
+    #[case("90909 \t", 5)]
+    #[case("", 0)]
+    fn test_parse_disk_map(#[case] line: &str, #[case] count: usize) {
+        assert_eq!(parse_disk_map(line).map(|items| items.len()), Ok(count));
+    }
+
+    #[rstest]
+    #[case("12a45", "a at position 2")]
+    #[case("1\r2", "\\r at position 1")]
+    #[case("-1", "- at position 0")]
+    fn test_parse_invalid_digit(#[case] line: &str, #[case] message: &str) {
+        assert_eq!(parse_disk_map(line).err(), Some(InvalidInput(message.to_owned())));
+    }
+
+    #[test]
+    fn test_dense_round_trip() -> Result<(), InvalidInput> {
+        let disk = disk_from_digits(&[1, 2, 3, 4, 5]);
+        assert_eq!(disk.to_dense().digits, "12345");
+        assert_eq!(disk.to_dense().to_disk()?, disk);
+
+        let moved = disk.with_files(vec![
+            Extent { file_id: 2, start: 2, size: 10 },
+            Extent { file_id: 0, start: 13, size: 1 },
+        ]);
+        let dense = moved.to_dense();
+
+        assert_eq!(dense, DenseMap { digits: "02901111".to_owned(), file_ids: vec![2, 2, 0] });
+        assert_eq!(dense.to_disk()?, moved);
+        Ok(())
+    }
+}
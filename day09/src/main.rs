@@ -1,12 +1,16 @@
-use std::iter::repeat_n;
+use std::env;
+use std::time::Instant;
 
-struct InputItem {
-    file_id: Option<usize>,
-    size: usize,
-}
+use aoc_tools::InvalidInput;
+use itertools::Itertools;
+use strategy::{BlockWise, CompactionStrategy, WholeFile};
+
+mod disk;
+mod strategy;
+
+use disk::Disk;
 
-#[derive(Clone, Copy)]
-struct FileSysItem {
+struct InputItem {
     file_id: Option<usize>,
     size: usize,
 }
@@ -17,6 +21,30 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    match env::args().nth(2).as_deref() {
+        Some("--bench") => {
+            benchmark_strategies(&parsed);
+            return Ok(());
+        },
+        Some("--compare") => {
+            compare_strategies(&parsed);
+            return Ok(());
+        },
+        Some("--render") => {
+            let name = env::args().nth(3).unwrap_or("blocks".to_owned());
+            let strategy = strategy::find_strategy(&name).ok_or(InvalidInput(name))?;
+            render_steps(&parsed, strategy.as_ref());
+            return Ok(());
+        },
+        Some("--dense") => {
+            let name = env::args().nth(3).unwrap_or("blocks".to_owned());
+            let strategy = strategy::find_strategy(&name).ok_or(InvalidInput(name))?;
+            print_dense(&parsed, strategy.as_ref())?;
+            return Ok(());
+        },
+        _ => {},
+    }
+
     let result1 = calculate_p1(&parsed)?;
     println!("Result p1: {}", result1);
 
@@ -29,161 +57,71 @@ fn main() -> anyhow::Result<()> {
 fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
     let in_str = input.read_single_line()?;
 
-    // 2333133121414131402
-    let mut in_c = in_str.chars().into_iter();
-    let mut file_id = 0;
-
-    let mut file_desc = Vec::new();
-
-    while let Some(file_len) = in_c.next() {
-        file_desc.push(InputItem {
-            file_id: Some(file_id),
-            size: parse_char(file_len),
-        });
-        file_id += 1;
-
-        if let Some(space_len) = in_c.next() {
-            file_desc.push(InputItem {
-                file_id: None,
-                size: parse_char(space_len),
-            });
-        } else {
-            break;
-        }
-    }
-
-    Ok(file_desc)
-}
-
-fn parse_char(c: char) -> usize {
-    format!("{}", c).parse().unwrap()
+    Ok(disk::parse_disk_map(&in_str)?)
 }
 
 fn calculate_p1(input: &ParsedInput) -> anyhow::Result<u64> {
-    let mut dm = expand_disk_map(input);
-    let disk_map = dm.as_mut_slice();
-
-    let mut start_idx = 0;
-    let mut end_idx = disk_map.len() - 1;
-
-    while start_idx < end_idx {
-        while disk_map[start_idx].file_id.is_some() {
-            start_idx += 1;
-        }
-
-        while disk_map[end_idx].file_id.is_none() {
-            end_idx -= 1;
-        }
-
-        if start_idx >= end_idx { break; }
-
-        disk_map[start_idx] = disk_map[end_idx];
-        disk_map[end_idx] = FileSysItem {
-            file_id: None,
-            size: 0,
-        };
-
-        start_idx += 1;
-        end_idx -= 1;
-    }
-
-    Ok(calculate_disk_checksum(&disk_map))
-}
+    let disk = Disk::from_items(input);
 
-fn expand_disk_map(input: &ParsedInput) -> Vec<FileSysItem> {
-    let mut disk_map = Vec::new();
-
-    for item in input.into_iter() {
-        disk_map.extend(repeat_n(
-            FileSysItem {
-                file_id: item.file_id,
-                size: item.size
-            },
-            item.size)
-        );
-    }
-
-    disk_map
-}
-
-fn calculate_disk_checksum(disk_map: &[FileSysItem]) -> u64 {
-    disk_map
-        .into_iter()
-        .enumerate()
-        .map(|(pos, item)| pos as u64 * item.file_id.unwrap_or(0) as u64 )
-        .sum()
+    Ok(BlockWise.compact(&disk, None).checksum())
 }
 
 fn calculate_p2(input: &ParsedInput) -> anyhow::Result<u64> {
-    let mut dm = expand_disk_map(input);
-    let mut disk_map = dm.as_mut_slice();
+    let disk = Disk::from_items(input);
 
-    let mut block_idx = disk_map.len() - 1;
+    Ok(WholeFile.compact(&disk, None).checksum())
+}
 
-    loop {
-        while disk_map[block_idx].file_id.is_none() {
-            block_idx -= 1;
-        }
+fn compare_strategies(input: &ParsedInput) {
+    let disk = Disk::from_items(input);
 
-        let block_size = disk_map[block_idx].size;
-        block_idx -= block_size - 1;
+    println!("{} blocks, {} files", disk.size(), disk.files().len());
+    println!("{:<10} {:>20} {:>8} {:>11} {:>7} {:>13} {:>9}",
+        "strategy", "checksum", "extents", "fragmented", "holes", "largest free", "used end");
 
-        let mut free_idx = 0;
+    for strategy in strategy::all_strategies() {
+        let compacted = strategy.compact(&disk, None);
+        let stats = compacted.fragmentation();
 
-        while free_idx < disk_map.len() && not_fit(&disk_map[free_idx], block_size) {
-            free_idx += 1;
-        }
+        println!("{:<10} {:>20} {:>8} {:>11} {:>7} {:>13} {:>9}",
+            strategy.name(), compacted.checksum(), stats.extents, stats.fragmented_files,
+            stats.holes, stats.largest_free, stats.used_end);
+    }
+}
 
-        if free_idx < disk_map.len() && block_idx > free_idx {
-            let free_size = disk_map[free_idx].size;
+fn benchmark_strategies(input: &ParsedInput) {
+    const ROUNDS: u32 = 20;
 
-            move_block(&mut disk_map, free_idx, block_idx, block_size);
-            resize_remaining_free(&mut disk_map, free_idx + block_size, free_size - block_size);
-        }
-        if block_idx == 0 {
-            break;
+    let disk = Disk::from_items(input);
+    for strategy in strategy::all_strategies() {
+        let started = Instant::now();
+        let mut checksum = 0;
+        for _ in 0..ROUNDS {
+            checksum = strategy.compact(&disk, None).checksum();
         }
-        block_idx -= 1;
+        println!("{:<10} {:>20} {:>10.3?}", strategy.name(), checksum, started.elapsed() / ROUNDS);
     }
-
-    Ok(calculate_disk_checksum(&disk_map))
 }
 
-fn not_fit(block: &FileSysItem, wanted: usize) -> bool {
-    block.file_id.is_some() || block.size < wanted
-}
-
-fn move_block(disk_map: &mut [FileSysItem], free_idx: usize, block_idx: usize, block_size: usize) {
-    for i in 0..block_size {
-        disk_map[free_idx + i] = disk_map[block_idx + i];
+fn render_steps(input: &ParsedInput, strategy: &dyn CompactionStrategy) {
+    let disk = Disk::from_items(input);
 
-        disk_map[block_idx + i] = FileSysItem {
-            file_id: None,
-            size: block_size,
-        };
-    }
+    println!("{}", disk);
+    strategy.compact(&disk, Some(&mut |d: &Disk| println!("{}", d)));
 }
 
-fn resize_remaining_free(disk_map: &mut [FileSysItem], free_idx: usize, free_size: usize) {
-    for i in 0..free_size {
-        disk_map[free_idx + i] = FileSysItem {
-            file_id: None,
-            size: free_size,
-        };
-    }
-}
+fn print_dense(input: &ParsedInput, strategy: &dyn CompactionStrategy) -> anyhow::Result<()> {
+    let compacted = strategy.compact(&Disk::from_items(input), None);
+    let dense = compacted.to_dense();
 
+    // the checksum is taken from the map as read back, to show nothing got lost
+    println!("{}", dense.digits);
+    println!("{}", dense.file_ids.iter().join(","));
+    println!("checksum: {}", dense.to_disk()?.checksum());
 
-// fn print_map(disk_map: &Vec<(Option<usize>, usize)>) {
-//     for (p, _) in disk_map.into_iter() {
-//         print!("{}", match p{
-//             Some(d) => format!("{}", d),
-//             None => ".".to_string(),
-//         });
-//     }
+    Ok(())
+}
 
-//     println!();
-// }
 
 #[cfg(test)]
 mod tests {
@@ -214,8 +152,6 @@ mod tests {
     fn test_sample_p2(#[case] (parsed, _, expected): (ParsedInput, Option<u64>, Option<u64>)) -> anyhow::Result<()> {
         let result2 = calculate_p2(&parsed)?;
 
-        println!("{:?}", expected);
-
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
     }
@@ -0,0 +1,303 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::disk::{Disk, Extent};
+
+pub type Observer<'a> = Option<&'a mut dyn FnMut(&Disk)>;
+
+pub trait CompactionStrategy {
+    fn name(&self) -> &'static str;
+
+    /// Returns the compacted layout. The observer, if any, sees the layout after every move,
+    /// which is expensive and only meant for rendering small disks.
+    fn compact(&self, disk: &Disk, observer: Observer) -> Disk;
+}
+
+/// Moves single blocks from the end of the disk into the leftmost free space.
+pub struct BlockWise;
+
+/// Moves each file as a whole, highest position first, into the leftmost free span that fits.
+pub struct WholeFile;
+
+/// Like `WholeFile`, but picks the smallest span that fits, leaving larger ones for larger files.
+pub struct BestFit;
+
+/// Packs whole files towards the end of the disk instead of the start.
+pub struct MoveToEnd;
+
+pub fn all_strategies() -> Vec<Box<dyn CompactionStrategy>> {
+    vec![
+        Box::new(BlockWise),
+        Box::new(WholeFile),
+        Box::new(BestFit),
+        Box::new(MoveToEnd),
+    ]
+}
+
+pub fn find_strategy(name: &str) -> Option<Box<dyn CompactionStrategy>> {
+    all_strategies()
+        .into_iter()
+        .find(|s| s.name() == name)
+}
+
+impl CompactionStrategy for BlockWise {
+    fn name(&self) -> &'static str {
+        "blocks"
+    }
+
+    fn compact(&self, disk: &Disk, mut observer: Observer) -> Disk {
+        let mut pending = disk.files().to_vec();
+        let mut moved = Vec::new();
+
+        'spans: for (span_start, span_size) in disk.free_spans() {
+            let mut pos = span_start;
+            let span_end = span_start + span_size;
+
+            while pos < span_end {
+                let Some(file) = pending.last_mut() else { break 'spans };
+                if file.start < pos {
+                    break 'spans;
+                }
+
+                // when watched, go block by block to show every step
+                let count = if observer.is_some() { 1 } else { file.size.min(span_end - pos) };
+                moved.push(Extent { file_id: file.file_id, start: pos, size: count });
+                pos += count;
+
+                // blocks are taken from the tail, the head of the file stays in place
+                file.size -= count;
+                if file.size == 0 {
+                    pending.pop();
+                }
+
+                if let Some(obs) = observer.as_mut() {
+                    obs(&disk.with_files(pending.iter().chain(moved.iter()).copied().collect()));
+                }
+            }
+        }
+
+        pending.extend(moved);
+        disk.with_files(pending)
+    }
+}
+
+impl CompactionStrategy for WholeFile {
+    fn name(&self) -> &'static str {
+        "files"
+    }
+
+    fn compact(&self, disk: &Disk, observer: Observer) -> Disk {
+        // leftmost fitting span: the lowest start among all heaps that are long enough
+        move_whole_files(disk, observer, |candidates| candidates.min())
+    }
+}
+
+impl CompactionStrategy for BestFit {
+    fn name(&self) -> &'static str {
+        "best-fit"
+    }
+
+    fn compact(&self, disk: &Disk, observer: Observer) -> Disk {
+        // candidates come shortest span first, take the first one that lies before the file
+        move_whole_files(disk, observer, |candidates| candidates.next())
+    }
+}
+
+impl CompactionStrategy for MoveToEnd {
+    fn name(&self) -> &'static str {
+        "to-end"
+    }
+
+    fn compact(&self, disk: &Disk, observer: Observer) -> Disk {
+        let Some(obs) = observer else {
+            return WholeFile.compact(&disk.mirrored(), None).mirrored();
+        };
+
+        let mut mirrored_observer = |d: &Disk| obs(&d.mirrored());
+        WholeFile.compact(&disk.mirrored(), Some(&mut mirrored_observer)).mirrored()
+    }
+}
+
+/// Free spans are kept in one min-heap per length. `choose` gets (start, length) of the
+/// leftmost span of each length that fits the file and lies before it, shortest length first.
+fn move_whole_files<F>(disk: &Disk, mut observer: Observer, choose: F) -> Disk
+    where F: Fn(&mut dyn Iterator<Item = (usize, usize)>) -> Option<(usize, usize)>
+{
+    let spans = disk.free_spans();
+    let max_len = spans.iter().map(|(_, size)| *size).max().unwrap_or(0);
+
+    let mut by_len: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); max_len + 1];
+    for (start, size) in spans {
+        by_len[size].push(Reverse(start));
+    }
+
+    let mut files = disk.files().to_vec();
+
+    for idx in (0..files.len()).rev() {
+        let file = files[idx];
+
+        let mut candidates = (file.size..by_len.len())
+            .filter_map(|len| by_len[len].peek().map(|Reverse(start)| (*start, len)))
+            .filter(|(start, _)| *start < file.start);
+
+        if let Some((start, len)) = choose(&mut candidates) {
+            by_len[len].pop();
+            files[idx].start = start;
+            if len > file.size {
+                by_len[len - file.size].push(Reverse(start + file.size));
+            }
+
+            if let Some(obs) = observer.as_mut() {
+                obs(&disk.with_files(files.clone()));
+            }
+        }
+    }
+
+    disk.with_files(files)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use crate::disk::tests::disk_from_digits;
+    use super::*;
+
+    const SAMPLE: [usize; 19] = [2, 3, 3, 3, 1, 3, 3, 1, 2, 1, 4, 1, 4, 1, 3, 1, 4, 0, 2];
+
+    fn pseudo_random_digits(count: usize, mut seed: u64) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((seed >> 33) % 10) as usize
+            })
+            .collect()
+    }
+
+    // one entry per block, compacted the straightforward way
+    fn expand(disk: &Disk) -> Vec<Option<usize>> {
+        let mut blocks = vec![None; disk.size()];
+        for f in disk.files() {
+            blocks[f.start..f.end()].fill(Some(f.file_id));
+        }
+        blocks
+    }
+
+    fn naive_blocks(mut blocks: Vec<Option<usize>>) -> Vec<Option<usize>> {
+        let (mut start, mut end) = (0, blocks.len());
+        while start < end {
+            if blocks[start].is_some() {
+                start += 1;
+            } else if blocks[end - 1].is_none() {
+                end -= 1;
+            } else {
+                blocks.swap(start, end - 1);
+            }
+        }
+        blocks
+    }
+
+    fn naive_files(mut blocks: Vec<Option<usize>>, files: &[Extent]) -> Vec<Option<usize>> {
+        for f in files.iter().rev() {
+            let fits = (0..f.start).find(|s| blocks[*s..*s + f.size].iter().all(|b| b.is_none()));
+            if let Some(s) = fits {
+                blocks[f.start..f.end()].fill(None);
+                blocks[s..s + f.size].fill(Some(f.file_id));
+            }
+        }
+        blocks
+    }
+
+    // first fit over the list of free spans. Spans only ever shrink, so the first one
+    // that is long enough for a size never moves left and a cursor per size will do
+    fn span_list_files(disk: &Disk) -> Disk {
+        let mut spans = disk.free_spans();
+        let mut files = disk.files().to_vec();
+        let mut first_fit = vec![0; files.iter().map(|f| f.size).max().unwrap_or(0) + 1];
+
+        for file in files.iter_mut().rev() {
+            let cursor = &mut first_fit[file.size];
+            while spans.get(*cursor).is_some_and(|(_, size)| *size < file.size) {
+                *cursor += 1;
+            }
+
+            if let Some((start, size)) = spans.get_mut(*cursor).filter(|(start, _)| *start < file.start) {
+                file.start = *start;
+                *start += file.size;
+                *size -= file.size;
+            }
+        }
+
+        disk.with_files(files)
+    }
+
+    fn block_checksum(blocks: &[Option<usize>]) -> u64 {
+        blocks.iter().enumerate().map(|(pos, id)| (pos * id.unwrap_or(0)) as u64).sum()
+    }
+
+    #[rstest]
+    #[case(&BlockWise, "0099811188827773336446555566..............", 1928)]
+    #[case(&WholeFile, "00992111777.44.333....5555.6666.....8888..", 2858)]
+    #[case(&BestFit, "00992111777.44.333....5555.6666.....8888..", 2858)]
+    #[case(&MoveToEnd, "........111..00333.44.5555.6666.7772888899", 4173)]
+    fn test_sample_layouts(#[case] strategy: &dyn CompactionStrategy, #[case] layout: &str, #[case] checksum: u64) {
+        let compacted = strategy.compact(&disk_from_digits(&SAMPLE), None);
+
+        assert_eq!(compacted.to_string(), layout);
+        assert_eq!(compacted.checksum(), checksum);
+    }
+
+    #[test]
+    fn test_best_fit_keeps_large_span() {
+        let disk = disk_from_digits(&[1, 3, 1, 1, 3, 0, 1]);
+
+        assert_eq!(disk.to_string(), "0...1.2223");
+        assert_eq!(WholeFile.compact(&disk, None).to_string(), "031...222.");
+        assert_eq!(BestFit.compact(&disk, None).to_string(), "022213....");
+    }
+
+    #[test]
+    fn test_observed_steps() {
+        let mut steps = Vec::new();
+
+        BlockWise.compact(&disk_from_digits(&[1, 2, 3, 4, 5]), Some(&mut |d: &Disk| steps.push(d.to_string())));
+
+        assert_eq!(steps, vec![
+            "02.111....2222.",
+            "022111....222..",
+            "0221112...22...",
+            "02211122..2....",
+            "022111222......",
+        ]);
+    }
+
+    #[test]
+    fn test_matches_block_simulation() {
+        for seed in 0..20 {
+            let disk = disk_from_digits(&pseudo_random_digits(301, seed));
+
+            assert_eq!(expand(&BlockWise.compact(&disk, None)), naive_blocks(expand(&disk)));
+            assert_eq!(expand(&WholeFile.compact(&disk, None)), naive_files(expand(&disk), disk.files()));
+            assert_eq!(expand(&span_list_files(&disk)), naive_files(expand(&disk), disk.files()));
+        }
+    }
+
+    #[test]
+    fn test_large_map() {
+        // ten times the puzzle input, 100k files
+        let digits = pseudo_random_digits(200_001, 42);
+        let disk = disk_from_digits(&digits);
+
+        let blocks = BlockWise.compact(&disk, None);
+        assert_eq!(blocks.checksum(), block_checksum(&naive_blocks(expand(&disk))));
+
+        // whole files never overlap and only ever move left
+        let files = WholeFile.compact(&disk, None);
+        let mut moved = files.files().to_vec();
+        assert!(moved.windows(2).all(|w| w[0].end() <= w[1].start));
+        moved.sort_by_key(|f| f.file_id);
+        assert_eq!(moved.len(), disk.files().len());
+        assert!(moved.iter().zip(disk.files()).all(|(f, orig)| f.file_id == orig.file_id && f.start <= orig.start));
+
+        assert_eq!(files, span_list_files(&disk));
+    }
+}
@@ -1,5 +1,10 @@
-use aoc_tools::{Direction, Grid, Point};
-use std::collections::HashSet;
+use aoc_tools::{Grid, Point};
+use itertools::Itertools;
+use std::env;
+
+mod trails;
+
+use trails::Trails;
 
 type ParsedInput = (Grid<char>, Vec<Point>);
 
@@ -7,6 +12,11 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--trailheads") {
+        print_trailheads(&parsed);
+        return Ok(());
+    }
+
     let result1 = calculate_p1(&parsed);
     println!("Result p1: {}", result1);
 
@@ -33,55 +43,41 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
 
 fn calculate_p1(input: &ParsedInput) -> usize {
     let (grid, starts) = input;
+    let trails = climb_to_nine(grid);
 
-    let mut total = 0;
-
-    for pos in starts.into_iter() {
-        let mut reached: HashSet<Point> = HashSet::new();
-        walk_path(&grid, *pos, '0', &mut reached);
-
-        total += reached.len()
-    }
-
-    total
+    starts
+        .iter()
+        .map(|pos| trails.reachable(*pos).len())
+        .sum()
 }
 
+fn calculate_p2(input: &ParsedInput) -> u64 {
+    let (grid, starts) = input;
+    let trails = climb_to_nine(grid);
 
-fn walk_path(grid: &Grid<char>, pos: Point, current_step: char, reached: &mut HashSet<Point>) -> usize {
-
-    if grid[pos] != current_step {
-        return 0;
-    }
-
-    if current_step == '9' {
-        reached.insert(pos);
-        return 1;
-    }
-
-    let mut score = 0;
-
-    let next_step = (current_step as u8 + 1) as char;
-
-    for dir in Direction::all() {
-        if let Some(next) = pos.advance(dir, grid.size()) {
-            score += walk_path(grid, next, next_step, reached);
-        }
-    }
-
-    score
+    starts
+        .iter()
+        .map(|pos| trails.count(*pos))
+        .sum()
 }
 
-
-
-fn calculate_p2(input: &ParsedInput) -> usize {
+fn print_trailheads(input: &ParsedInput) {
     let (grid, starts) = input;
+    let trails = climb_to_nine(grid);
+
+    for pos in starts {
+        let summits = trails
+            .reachable(*pos)
+            .ids()
+            .map(|id| trails.summit(id))
+            .map(|s| format!("{},{}", s.x, s.y))
+            .join(" ");
+        println!("{},{}: rating {}, summits {}", pos.x, pos.y, trails.count(*pos), summits);
+    }
+}
 
-    let mut reached: HashSet<Point> = HashSet::new();
-
-    starts
-        .into_iter()
-        .map(|pos| walk_path(&grid, *pos, '0', &mut reached))
-        .sum()
+fn climb_to_nine(grid: &Grid<char>) -> Trails {
+    Trails::climb(grid, |c| c.to_digit(10), 9)
 }
 
 #[cfg(test)]
@@ -117,7 +113,7 @@ mod tests {
 
         let result2 = calculate_p2(&parsed);
 
-        assert_eq!(expected, Some(result2 as u64));
+        assert_eq!(expected, Some(result2));
         Ok(())
     }
 }
@@ -0,0 +1,155 @@
+use aoc_tools::{Direction, Grid, Point};
+
+/// Summits reachable from a cell, one bit per summit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummitSet {
+    words: Vec<u64>,
+}
+
+impl SummitSet {
+    fn empty(summits: usize) -> Self {
+        Self {
+            words: vec![0; summits.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, id: usize) {
+        self.words[id / 64] |= 1 << (id % 64);
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        for (w, o) in self.words.iter_mut().zip(&other.words) {
+            *w |= o;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(i, w)| (0..64).filter(move |b| w & (1 << b) != 0).map(move |b| i * 64 + b))
+    }
+}
+
+/// Every walk over a grid that goes up by exactly one per step, from any cell up to `peak`.
+/// Filled layer by layer from the peak down, so each cell is only looked at once.
+pub struct Trails {
+    width: usize,
+    counts: Vec<u64>,
+    reachable: Vec<SummitSet>,
+    summits: Vec<Point>,
+}
+
+impl Trails {
+    /// `height` gives the height of a cell, None for cells no trail can use.
+    pub fn climb<T, F>(grid: &Grid<T>, height: F, peak: u32) -> Self
+        where T: Default + Copy, F: Fn(&T) -> Option<u32>
+    {
+        let (width, rows) = grid.size();
+        let index = |p: Point| p.y * width + p.x;
+
+        let mut layers: Vec<Vec<Point>> = vec![Vec::new(); peak as usize + 1];
+        for (cell, pos) in grid.enumerate() {
+            if let Some(h) = height(cell).filter(|h| *h <= peak) {
+                layers[h as usize].push(pos);
+            }
+        }
+
+        let summits = layers[peak as usize].clone();
+        let mut trails = Self {
+            width,
+            counts: vec![0; width * rows],
+            reachable: vec![SummitSet::empty(summits.len()); width * rows],
+            summits,
+        };
+
+        for (id, pos) in trails.summits.iter().enumerate() {
+            trails.counts[index(*pos)] = 1;
+            trails.reachable[index(*pos)].insert(id);
+        }
+
+        for h in (0..peak).rev() {
+            for pos in &layers[h as usize] {
+                let mut count = 0;
+                let mut reachable = SummitSet::empty(trails.summits.len());
+
+                let higher = Direction::all()
+                    .filter_map(|dir| pos.advance(dir, grid.size()))
+                    .filter(|next| height(&grid[*next]) == Some(h + 1));
+                for next in higher {
+                    count += trails.counts[index(next)];
+                    reachable.union_with(&trails.reachable[index(next)]);
+                }
+
+                trails.counts[index(*pos)] = count;
+                trails.reachable[index(*pos)] = reachable;
+            }
+        }
+
+        trails
+    }
+
+    /// Number of distinct trails from `pos` to any summit.
+    pub fn count(&self, pos: Point) -> u64 {
+        self.counts[self.index(pos)]
+    }
+
+    pub fn reachable(&self, pos: Point) -> &SummitSet {
+        &self.reachable[self.index(pos)]
+    }
+
+    pub fn summit(&self, id: usize) -> Point {
+        self.summits[id]
+    }
+
+    fn index(&self, pos: Point) -> usize {
+        pos.y * self.width + pos.x
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_from(rows: &[&str]) -> Grid<char> {
+        Grid::try_from_lines(rows.iter().map(|r| Ok(r.to_string()))).unwrap()
+    }
+
+    #[test]
+    fn test_climb() {
+        let grid = grid_from(&[
+            "0123",
+            "1234",
+            "2345",
+        ]);
+        let trails = Trails::climb(&grid, |c| c.to_digit(10), 3);
+        let p = |x, y| Point { x, y };
+
+        // every monotone path from the corner to one of the three 3s
+        assert_eq!(trails.count(p(0, 0)), 1 + 3 + 3);
+        assert_eq!(trails.reachable(p(0, 0)).len(), 3);
+        assert_eq!(trails.count(p(2, 1)), 1);
+        assert_eq!(trails.reachable(p(3, 2)).len(), 0);
+
+        let seen: Vec<Point> = trails.reachable(p(1, 1)).ids().map(|id| trails.summit(id)).collect();
+        assert_eq!(seen, vec![p(2, 1), p(1, 2)]);
+    }
+
+    #[test]
+    fn test_many_summits() {
+        // more summits than fit a single word
+        let row = "01".repeat(50);
+        let grid = grid_from(&[&row, &row]);
+
+        let trails = Trails::climb(&grid, |c| c.to_digit(10), 1);
+
+        assert_eq!(trails.reachable(Point { x: 98, y: 0 }).ids().collect::<Vec<_>>(), vec![48, 49]);
+        assert_eq!(trails.count(Point { x: 2, y: 1 }), 2);
+        assert!(trails.reachable(Point { x: 1, y: 1 }).ids().eq([50]));
+    }
+}
@@ -1,5 +1,6 @@
-use aoc_tools::{IterMoreTools, ResultExt};
-use std::collections::HashMap;
+use aoc_tools::{Count, InvalidInput, IterMoreTools, NumExt, Population, ResultExt};
+use num::BigUint;
+use std::{env, iter};
 
 type ParsedInput = Vec<u64>;
 
@@ -7,10 +8,26 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
-    let result1 = calculate_p1(&parsed);
+    if env::args().nth(2).as_deref() == Some("--blinks") {
+        let blinks = env::args().nth(3).unwrap_or("75".to_owned());
+        let blinks = blinks.parse().map_err_to_invalid_input(&blinks)?;
+
+        // counts grow by about half each blink, u64 lasts for roughly 100 blinks
+        let count = match env::args().nth(4).as_deref() {
+            None | Some("u64") => count_after_blinks::<u64>(&parsed, blinks)?.to_string(),
+            Some("u128") => count_after_blinks::<u128>(&parsed, blinks)?.to_string(),
+            Some("big") => count_after_blinks::<BigUint>(&parsed, blinks)?.to_string(),
+            Some(other) => Err(InvalidInput(other.to_owned()))?,
+        };
+
+        println!("Stones after {} blinks: {}", blinks, count);
+        return Ok(());
+    }
+
+    let result1 = calculate_p1(&parsed)?;
     println!("Result p1: {}", result1);
 
-    let result2 = calculate_p2(&parsed);
+    let result2 = calculate_p2(&parsed)?;
     println!("Result p2: {}", result2);
 
     Ok(())
@@ -28,72 +45,44 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
     Ok(parsed)
 }
 
-fn calculate_p1(input: &ParsedInput) -> usize {
-    let mut pebbles = input.clone();
-
-    for _ in 0..25 {
-        let mut new_row: Vec<u64> = Vec::with_capacity(pebbles.len() * 2);
-
-        for p in pebbles.into_iter() {
-            if p == 0 {
-                new_row.push(1);
-            } else if let Some((np1, np2)) = split_pebble(p) {
-                new_row.push(np1);
-                new_row.push(np2);
-            } else {
-                new_row.push(p * 2024);
-            }
-        }
-
-        pebbles = new_row;
-    }
+fn calculate_p1(input: &ParsedInput) -> anyhow::Result<u64> {
+    count_after_blinks(input, 25)
+}
 
-    pebbles.len()
+fn calculate_p2(input: &ParsedInput) -> anyhow::Result<u64> {
+    count_after_blinks(input, 75)
 }
 
-fn split_pebble(p: u64) -> Option<(u64, u64)> {
-    let s = format!("{}", p);
-    let l = s.len();
+fn count_after_blinks<C: Count>(input: &ParsedInput, blinks: usize) -> anyhow::Result<C> {
+    let stones: Population<u64, C> = Population::from_items(input.iter().copied())?;
 
-    if (l & 1) == 0 {
-        let p1 = s[..l/2].parse().unwrap();
-        let p2 = s[l/2..].parse().unwrap();
+    stones
+        .try_evolve_n(blinks, blink)
+        .and_then(|evolved| Ok(evolved.total()?))
+        .map_err(|e| anyhow::anyhow!("{} after {} blinks", e, blinks))
+}
 
-        Some((p1, p2))
+fn blink(p: &u64) -> anyhow::Result<impl Iterator<Item = u64>> {
+    let (first, second) = if *p == 0 {
+        (1, None)
+    } else if let Some((np1, np2)) = split_pebble(*p) {
+        (np1, Some(np2))
     } else {
-        None
-    }
+        let grown = p.checked_mul(2024).ok_or_else(|| anyhow::anyhow!("stone {} is too large to multiply by 2024", p))?;
+        (grown, None)
+    };
+
+    Ok(iter::once(first).chain(second))
 }
 
-fn calculate_p2(input: &ParsedInput) -> usize {
-
-    let mut pebbles: HashMap<u64, usize> = input
-        .into_iter()
-        .map(|p| (*p, 1))
-        .collect();
-
-    for _ in 0..75 {
-        let mut new_row: HashMap<u64, usize> = HashMap::new();
-
-        for (p, count) in pebbles.into_iter() {
-            if p == 0 {
-                *new_row.entry(1).or_insert(0) += count;
-            } else if let Some((np1, np2)) = split_pebble(p) {
-                *new_row.entry(np1).or_insert(0) += count;
-                *new_row.entry(np2).or_insert(0) += count;
-            } else {
-                let np = p * 2024;
-                *new_row.entry(np).or_insert(0) += count;
-            }
-        }
+fn split_pebble(p: u64) -> Option<(u64, u64)> {
+    let digits = p.digit_count();
 
-        pebbles = new_row;
+    if (digits & 1) == 0 {
+        Some(p.split_digits(digits / 2))
+    } else {
+        None
     }
-
-    pebbles
-        .into_iter()
-        .map(|(_, count)|count)
-        .sum()
 }
 
 #[cfg(test)]
@@ -114,7 +103,7 @@ mod tests {
     #[case(load_sample("input.txt")?)]
     fn test_sample_p1(#[case] (parsed, expected, _): (ParsedInput, Option<u64>, Option<u64>)) -> anyhow::Result<()> {
 
-        let result1 = calculate_p1(&parsed);
+        let result1 = calculate_p1(&parsed)?;
 
         assert_eq!(expected, Some(result1 as u64));
         Ok(())
@@ -125,9 +114,43 @@ mod tests {
     #[case(load_sample("input.txt")?)]
     fn test_sample_p2(#[case] (parsed, _, expected): (ParsedInput, Option<u64>, Option<u64>)) -> anyhow::Result<()> {
 
-        let result2 = calculate_p2(&parsed);
+        let result2 = calculate_p2(&parsed)?;
 
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
     }
+
+    #[rstest]
+    #[case(&[125, 17], 6)]
+    #[case(&[0, 1, 10, 99, 999], 12)]
+    fn test_matches_single_stones(#[case] stones: &[u64], #[case] blinks: usize) -> anyhow::Result<()> {
+        let mut naive = stones.to_vec();
+        for _ in 0..blinks {
+            naive = naive.iter().map(blink).collect::<anyhow::Result<Vec<_>>>()?.into_iter().flatten().collect();
+        }
+
+        assert_eq!(count_after_blinks::<usize>(&stones.to_vec(), blinks)?, naive.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_overflow() -> anyhow::Result<()> {
+        let stones = vec![125, 17];
+
+        let exact = count_after_blinks::<BigUint>(&stones, 120)?;
+
+        assert!(count_after_blinks::<u64>(&stones, 120).is_err());
+        assert_eq!(BigUint::from(count_after_blinks::<u128>(&stones, 120)?), exact);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stone_overflow() {
+        // 17 digits, and 2024 times it no longer fits a u64
+        let stones = vec![10_000_000_000_000_001];
+
+        let err = count_after_blinks::<u64>(&stones, 1).unwrap_err();
+
+        assert_eq!(err.to_string(), "stone 10000000000000001 is too large to multiply by 2024 after 1 blinks");
+    }
 }
@@ -0,0 +1,140 @@
+use aoc_tools::ext_gcd;
+
+/// Cheapest non-negative whole (a, b) with a * va + b * vb = target, each count at most `limit`.
+/// Parallel vectors leave a whole line of solutions, on which the cheapest one is picked.
+pub fn cheapest_combination(va: (i128, i128), vb: (i128, i128), target: (i128, i128), cost: (i128, i128), limit: Option<i128>) -> Option<(i128, i128)> {
+    let ((ax, ay), (bx, by), (px, py)) = (va, vb, target);
+    let within = |n: i128| n >= 0 && limit.is_none_or(|l| n <= l);
+
+    let det = ax * by - ay * bx;
+    if det != 0 {
+        let (a_num, b_num) = (px * by - py * bx, ax * py - ay * px);
+        if a_num % det != 0 || b_num % det != 0 {
+            return None;
+        }
+
+        let (a, b) = (a_num / det, b_num / det);
+        return (within(a) && within(b)).then_some((a, b));
+    }
+
+    // parallel: the target must lie on the same line, then one of the equations is enough
+    if ax * py != ay * px || bx * py != by * px {
+        return None;
+    }
+
+    let (ca, cb, c) = if (ax, bx) != (0, 0) { (ax, bx, px) } else { (ay, by, py) };
+    if (ca, cb) == (0, 0) {
+        return (c == 0).then_some((0, 0));
+    }
+
+    cheapest_on_line(ca, cb, c, cost, limit)
+}
+
+// all solutions of ca * a + cb * b = c are (a0 + k * step_a, b0 - k * step_b)
+fn cheapest_on_line(ca: i128, cb: i128, c: i128, (cost_a, cost_b): (i128, i128), limit: Option<i128>) -> Option<(i128, i128)> {
+    let (g, x, y) = ext_gcd(ca, cb);
+    if c % g != 0 {
+        return None;
+    }
+
+    let (a0, b0) = (x * (c / g), y * (c / g));
+    let (step_a, step_b) = (cb / g, ca / g);
+
+    // each bound on a or b restricts k to one side
+    let mut k_min = i128::MIN;
+    let mut k_max = i128::MAX;
+    let mut restrict = |value: i128, step: i128, bound: i128, lower: bool| {
+        // value + k * step >= bound (lower) or <= bound
+        if step == 0 {
+            let ok = if lower { value >= bound } else { value <= bound };
+            if !ok {
+                k_min = i128::MAX;
+                k_max = i128::MIN;
+            }
+        } else if (step > 0) == lower {
+            k_min = k_min.max(div_ceil(bound - value, step));
+        } else {
+            k_max = k_max.min(div_floor(bound - value, step));
+        }
+    };
+
+    restrict(a0, step_a, 0, true);
+    restrict(b0, -step_b, 0, true);
+    if let Some(l) = limit {
+        restrict(a0, step_a, l, false);
+        restrict(b0, -step_b, l, false);
+    }
+
+    if k_min > k_max {
+        return None;
+    }
+
+    // the cost is linear in k, so the cheapest solution sits at one end of the range
+    let slope = cost_a * step_a - cost_b * step_b;
+    let k = match slope {
+        0 => if k_min > i128::MIN { k_min } else { k_max },
+        s if s > 0 => k_min,
+        _ => k_max,
+    };
+
+    if k == i128::MIN || k == i128::MAX {
+        // unbounded in the cheap direction, which non-negative counts never allow
+        return None;
+    }
+
+    Some((a0 + k * step_a, b0 - k * step_b))
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) == (b < 0)) { q + 1 } else { q }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    fn brute_force(va: (i128, i128), vb: (i128, i128), target: (i128, i128), cost: (i128, i128), limit: i128) -> Option<(i128, i128)> {
+        (0..=limit)
+            .flat_map(|a| (0..=limit).map(move |b| (a, b)))
+            .filter(|(a, b)| a * va.0 + b * vb.0 == target.0 && a * va.1 + b * vb.1 == target.1)
+            .min_by_key(|(a, b)| (a * cost.0 + b * cost.1, *a))
+    }
+
+    #[rstest]
+    #[case((94, 34), (22, 67), (8400, 5400), Some((80, 40)))]
+    #[case((26, 66), (67, 21), (12748, 12176), None)]
+    #[case((2, 2), (3, 3), (12, 12), Some((0, 4)))]
+    #[case((2, 2), (7, 7), (12, 12), Some((6, 0)))]
+    #[case((3, 6), (1, 2), (10, 20), Some((0, 10)))]
+    #[case((9, 3), (1, 0), (10, 20), None)]
+    fn test_cheapest(#[case] va: (i128, i128), #[case] vb: (i128, i128), #[case] target: (i128, i128), #[case] expected: Option<(i128, i128)>) {
+        assert_eq!(cheapest_combination(va, vb, target, (3, 1), None), expected);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let vectors = [(0, 0), (1, 0), (0, 2), (2, 4), (3, 6), (4, 1), (6, 12), (5, 5), (7, 3)];
+
+        for va in vectors {
+            for vb in vectors {
+                for target in [(0, 0), (12, 24), (30, 60), (21, 9), (35, 35), (17, 40)] {
+                    for cost in [(3, 1), (1, 3), (2, 2)] {
+                        let found = cheapest_combination(va, vb, target, cost, Some(40));
+                        let expected = brute_force(va, vb, target, cost, 40);
+
+                        let price = |r: Option<(i128, i128)>| r.map(|(a, b)| a * cost.0 + b * cost.1);
+                        assert_eq!(price(found), price(expected), "{:?} {:?} {:?} {:?}", va, vb, target, cost);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use regex::Regex;
-use std::cmp;
-use aoc_tools::{gauss_eliminate, ResultExt};
-use num::Rational64;
+use aoc_tools::ResultExt;
+
+mod diophantine;
+
+use diophantine::cheapest_combination;
+
+const COST_A: i128 = 3;
+const COST_B: i128 = 1;
+const P1_MAX_PRESSES: usize = 100;
+const P2_PRIZE_OFFSET: usize = 10000000000000;
 
 #[derive(Debug)]
 struct Machine {
@@ -85,75 +92,28 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
 }
 
 fn calculate_p1(input: &ParsedInput) -> usize {
-
     input
-        .into_iter()
-        .map(|m| find_costs(m))
+        .iter()
+        .filter_map(|m| cheapest_prize(m, 0, Some(P1_MAX_PRESSES)))
         .sum()
 }
 
-
-fn find_costs(machine: &Machine) -> usize {
-    let (px, py) = machine.prize;
-    let (sbx, sby) = machine.speed_b;
-    let (sax, say) = machine.speed_a;
-
-    let max_b = cmp::min(px / sbx, py / sby);
-    let max_a = cmp::min(px / sax, py / say);
-
-    for a in 0..=max_a {
-        for b in 0..=max_b {
-            let tx = a * sax + b * sbx;
-            let ty = a * say + b * sby;
-
-            if tx == px && ty == py {
-                return a * 3 + b;
-            }
-        }
-    }
-
-    0
-}
-
 fn calculate_p2(input: &ParsedInput) -> usize {
     input
-        .into_iter()
-        .map(|m| find_costs_2(m))
+        .iter()
+        .filter_map(|m| cheapest_prize(m, P2_PRIZE_OFFSET, None))
         .sum()
 }
 
-fn find_costs_2(machine: &Machine) -> usize {
-    let (px, py) = machine.prize;
-    let (sbx, sby) = machine.speed_b;
-    let (sax, say) = machine.speed_a;
-
-    let mut matrix: [[Rational64; 3]; 2] = [
-        [r(sax), r(sbx), r(px + 10000000000000)],
-        [r(say), r(sby), r(py + 10000000000000)],
-    ];
-
-    if gauss_eliminate(&mut matrix) {
-        if let Some(a) = check_round(matrix[0][2]) {
-            if let Some(b) = check_round(matrix[1][2]) {
-                return a * 3 + b;
-            }
-        }
-    }
-
-    0
-}
+/// Tokens needed to win the prize, if it can be won at all.
+fn cheapest_prize(machine: &Machine, offset: usize, limit: Option<usize>) -> Option<usize> {
+    let v = |(x, y): (usize, usize)| (x as i128, y as i128);
+    let (px, py) = v(machine.prize);
+    let target = (px + offset as i128, py + offset as i128);
 
-fn check_round(n: Rational64) -> Option<usize> {
+    let (a, b) = cheapest_combination(v(machine.speed_a), v(machine.speed_b), target, (COST_A, COST_B), limit.map(|l| l as i128))?;
 
-    if n.is_integer() && n >= Rational64::ZERO {
-        Some(n.to_integer() as usize)
-    } else {
-        None
-    }
-}
-
-fn r(n: usize) -> Rational64 {
-    (n as i64).into()
+    Some((a * COST_A + b * COST_B) as usize)
 }
 
 
@@ -192,6 +152,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parallel_buttons() {
+        let machine = Machine { speed_a: (4, 2), speed_b: (2, 1), prize: (10, 5) };
+        let off_line = Machine { speed_a: (4, 2), speed_b: (2, 1), prize: (11, 5) };
+
+        assert_eq!(cheapest_prize(&machine, 0, Some(100)), Some(5));
+        assert_eq!(cheapest_prize(&machine, 0, Some(2)), Some(7));
+        assert_eq!(cheapest_prize(&off_line, 0, None), None);
+    }
+
     // #[rstest]
     // #[case(14.9999847412109375, Some(15))]
     // #[case(3.0517578125e-5, Some(0))]
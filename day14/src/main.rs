@@ -1,4 +1,4 @@
-use aoc_tools::{Grid, IterMoreTools, Neighbours2D, ResultExt};
+use aoc_tools::{crt, ext_gcd, lcm_of, Grid, IterMoreTools, ResultExt};
 use itertools::{Itertools};
 use regex::Regex;
 
@@ -90,31 +90,34 @@ impl Robot {
 }
 
 fn calculate_p2(input: &ParsedInput, width: usize, height: usize) -> anyhow::Result<usize> {
-    for time in 0..(width * height) {
-        let mut positions: Grid<bool> = Grid::new(false, width, height);
-        
-        for robot in input.iter() {
-            positions[robot.position_after(time, width, height)] = true;
-        }
-
-        let mut nmatches = 0;
-        for (v, pos) in positions.enumerate() {
-            if *v {
-                for neigh in Neighbours2D::new_only_valid(pos.into(), (width as usize, height as usize), aoc_tools::NeighbourMap::Plus) {
-                    if positions[neigh] {
-                        nmatches += 1;
-                    }
-                }
-            }
-        }
+    // x and y move independently, each axis repeats after the lcm of the robots' periods on it
+    let x_period = lcm_of(input.iter().map(|r| axis_period(r.vx, width)));
+    let y_period = lcm_of(input.iter().map(|r| axis_period(r.vy, height)));
+
+    // the picture shows up when the robots huddle together along both axes at once
+    let tx = (0..x_period)
+        .min_by_key(|t| spread(input.iter().map(|r| r.position_after(*t, width, height).0)))
+        .unwrap_or(0);
+    let ty = (0..y_period)
+        .min_by_key(|t| spread(input.iter().map(|r| r.position_after(*t, width, height).1)))
+        .unwrap_or(0);
+
+    let (time, _) = crt([(tx as i64, x_period as i64), (ty as i64, y_period as i64)])
+        .ok_or_else(|| anyhow::anyhow!("Failed to calculate result"))?;
+
+    print_map_after(input, time as usize, width, height);
+    Ok(time as usize)
+}
 
-        if nmatches > input.len() {
-            print_map_after(input, time, width, height);
-            return Ok(time);
-        }
-    }
+fn axis_period(v: i32, size: usize) -> usize {
+    let (g, _, _) = ext_gcd(v as i64, size as i64);
+    size / g as usize
+}
 
-    Err(anyhow::anyhow!("Failed to calculate result"))
+/// Variance of the coordinates, scaled by the squared count to stay in integers.
+fn spread<I: Iterator<Item = usize>>(coords: I) -> u64 {
+    let (n, sum, sum_sq) = coords.fold((0, 0, 0), |(n, s, sq), c| (n + 1, s + c as u64, sq + (c * c) as u64));
+    n * sum_sq - sum * sum
 }
 
 fn draw_picture_p2(input: &ParsedInput, width: usize, height: usize) {
@@ -162,24 +165,6 @@ fn draw_picture_p2(input: &ParsedInput, width: usize, height: usize) {
     }
 }
 
-// fn cycle_length(robot: &Robot, width: i32, height: i32) -> usize {
-//     let mut r1px = robot.px;
-//     let mut r1py = robot.py;
-
-//     for t in 1..usize::MAX {
-        
-//         r1px = (r1px + width + robot.vx) % width;
-//         r1py = (r1py + height + robot.vy) % height;
-
-//         if r1px == robot.px && r1py == robot.py {
-//             return t;
-//         }
-//     }
-
-//     usize::MAX
-// }
-
-
 #[derive(Default, Clone, Copy)]
 struct FormattedCell(char);
 
@@ -241,4 +226,39 @@ mod tests {
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
     }
+
+    #[rstest]
+    #[case(31, 37, 777)]
+    #[case(101, 103, 6517)]
+    fn test_huddle_p2(#[case] width: usize, #[case] height: usize, #[case] huddle: usize) -> anyhow::Result<()> {
+        // robots that all gather in a small square at `huddle`, seen through random velocities
+        let mut seed = 11u32;
+        let mut next = |n: usize| { seed = seed.wrapping_mul(1103515245).wrapping_add(12345); (seed >> 16) as i32 % n as i32 };
+        let (w, h, t) = (width as i32, height as i32, huddle as i32);
+
+        let robots: ParsedInput = (0..150)
+            .map(|_| {
+                let (x, y) = (w / 2 + next(5), h / 2 + next(5));
+                let (vx, vy) = (1 + next(width - 1), 1 + next(height - 1));
+                Robot {
+                    px: (x - vx * t).rem_euclid(w),
+                    py: (y - vy * t).rem_euclid(h),
+                    vx,
+                    vy,
+                }
+            })
+            .collect();
+
+        assert_eq!(calculate_p2(&robots, width, height)?, huddle);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(3, 101, 101)]
+    #[case(-5, 10, 2)]
+    #[case(0, 103, 1)]
+    #[case(206, 103, 1)]
+    fn test_axis_period(#[case] v: i32, #[case] size: usize, #[case] expected: usize) {
+        assert_eq!(axis_period(v, size), expected);
+    }
 }
@@ -1,5 +1,5 @@
-use aoc_tools::{ResultExt, Point, Grid, Neighbours2D, NeighbourMap};
-use std::collections::{HashSet, BinaryHeap};
+use aoc_tools::{grid_bfs, DisjointSet, ResultExt, Point, Grid, Neighbours2D, NeighbourMap};
+use std::env;
 
 type ParsedInput = Vec<Point>;
 
@@ -7,6 +7,14 @@ fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--path") {
+        let nbytes = env::args().nth(3).unwrap_or("1024".to_owned());
+        let nbytes = nbytes.parse().map_err_to_invalid_input(&nbytes)?;
+        let size = env::args().nth(4).unwrap_or("70".to_owned());
+        let size = size.parse().map_err_to_invalid_input(&size)?;
+        return print_path(&parsed, size, size, nbytes);
+    }
+
     let result1 = calculate_p1(&parsed, 70, 70, 1024)?;
     println!("Result p1: {}", result1);
 
@@ -38,89 +46,82 @@ fn calculate_p1(input: &ParsedInput, width: usize, height: usize, nbytes: usize)
 }
 
 fn traverse_grid(input: &ParsedInput, width: usize, height: usize, nbytes: usize) -> Option<usize> {
+    let grid = fallen_bytes(input, width, height, nbytes);
 
+    grid_bfs(grid.size(), (0, 0).into(), |pos| grid[pos] != '#')
+        .distance((width, height).into())
+}
+
+fn fallen_bytes(input: &ParsedInput, width: usize, height: usize, nbytes: usize) -> Grid<char> {
     let mut grid: Grid<char> = Grid::new('.', width+1, height+1);
 
-    for p in input.into_iter().take(nbytes) {
+    for p in input.iter().take(nbytes) {
         grid[*p] = '#';
     }
 
-    let start_state = BfsState {
-        pos: (0, 0).into(),
-        score: 0,
-    };
-
-    let mut queue: BinaryHeap<BfsState> = BinaryHeap::new();
-    let mut queue_dedup: HashSet<BfsState> = HashSet::new();
-    let mut visited: HashSet<Point> = HashSet::new();
-    queue.push(start_state);
-
-    let mut best_score: Option<usize> = None;
-
-    while let Some(state) = queue.pop() {
-
-        visited.insert(state.pos);
-
-        if state.pos == (width, height).into() {
-            best_score = Some(state.score);
-            break;
-        }
+    grid
+}
 
-        let neigh = Neighbours2D::new_only_valid(state.pos.into(), grid.size(), NeighbourMap::Plus);
+/// Prints the memory space after `nbytes` bytes with a shortest path marked like the puzzle text.
+fn print_path(input: &ParsedInput, width: usize, height: usize, nbytes: usize) -> anyhow::Result<()> {
+    let mut grid = fallen_bytes(input, width, height, nbytes);
 
-        for n in neigh {
-            let new_state = BfsState {
-                pos: n.into(),
-                score: state.score + 1,
-            };
+    let path = grid_bfs(grid.size(), (0, 0).into(), |pos| grid[pos] != '#')
+        .path_to((width, height).into())
+        .ok_or_else(|| anyhow::anyhow!("Failed to traverse grid"))?;
 
-            if !visited.contains(&new_state.pos) && grid[new_state.pos] != '#' && !queue_dedup.contains(&new_state) {
-                queue.push(new_state.clone());
-                queue_dedup.insert(new_state);
-            }
-        }
+    for p in path.iter() {
+        grid[*p] = 'O';
     }
+    grid.print();
+    println!("{} steps", path.len() - 1);
 
-    best_score
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
-struct BfsState {
-    pos: Point,
-    score: usize,
+    Ok(())
 }
 
-impl Ord for BfsState {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.score.cmp(&self.score)
-    }
-}
+fn calculate_p2(input: &ParsedInput, width: usize, height: usize) -> anyhow::Result<String> {
+    let answer = first_blocking_byte(input, width, height).ok_or_else(|| anyhow::anyhow!("Failed to get answer"))?;
 
-impl PartialOrd for BfsState {
-    fn partial_cmp(&self, other: &BfsState) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+    Ok(format!("{},{}", answer.x, answer.y))
 }
 
-fn calculate_p2(input: &ParsedInput, width: usize, height: usize) -> anyhow::Result<String> {
-    let mut lo = 0;
-    let mut hi = input.len();
+/// The byte after which the exit can't be reached anymore. The way is cut exactly when
+/// fallen bytes, touching each other also diagonally, link the top or right wall to the
+/// left or bottom wall, which a single pass over the bytes can watch with a union-find.
+fn first_blocking_byte(input: &ParsedInput, width: usize, height: usize) -> Option<Point> {
+    let size = (width + 1, height + 1);
+    let index = |p: Point| p.y * size.0 + p.x;
+    let top_right = size.0 * size.1;
+    let bottom_left = top_right + 1;
+
+    let mut fallen: Grid<bool> = Grid::new(false, size.0, size.1);
+    let mut walls = DisjointSet::new(size.0 * size.1 + 2);
+
+    for byte in input {
+        if *byte == (0, 0).into() || *byte == (width, height).into() {
+            return Some(*byte);
+        }
 
-    while lo < hi {
-        let i = (lo + hi) / 2;
+        fallen[*byte] = true;
 
-        let steps = traverse_grid(input, width, height, i);
+        if byte.y == 0 || byte.x == width {
+            walls.union(index(*byte), top_right);
+        }
+        if byte.x == 0 || byte.y == height {
+            walls.union(index(*byte), bottom_left);
+        }
+        for n in Neighbours2D::new_only_valid((*byte).into(), size, NeighbourMap::All) {
+            if fallen[Point::from(n)] {
+                walls.union(index(*byte), index(n.into()));
+            }
+        }
 
-        if steps.is_some() {
-            lo = i + 1;
-        } else {
-            hi = i;
+        if walls.same(top_right, bottom_left) {
+            return Some(*byte);
         }
     }
 
-    let answer = input.get(lo - 1).ok_or_else(|| anyhow::anyhow!("Failed to get answer"))?;
-
-    Ok(format!("{},{}", answer.x, answer.y))
+    None
 }
 
 #[cfg(test)]
@@ -165,4 +166,16 @@ mod tests {
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
     }
+
+    #[rstest]
+    #[case(load_sample("sample.txt")?, 6)]
+    #[case(load_sample("input.txt")?, 70)]
+    fn test_blocking_byte_cuts_path(#[case] (parsed, _, _): (ParsedInput, Option<u64>, Option<u64>), #[case] size: usize) -> anyhow::Result<()> {
+        let byte = first_blocking_byte(&parsed, size, size).unwrap();
+        let fallen = parsed.iter().position(|p| *p == byte).unwrap() + 1;
+
+        assert!(traverse_grid(&parsed, size, size, fallen - 1).is_some());
+        assert!(traverse_grid(&parsed, size, size, fallen).is_none());
+        Ok(())
+    }
 }
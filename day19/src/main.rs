@@ -1,16 +1,29 @@
-use std::collections::HashMap;
+use aoc_tools::checked_sum;
+use std::env;
+use itertools::Itertools;
+use num::BigUint;
 use rayon::prelude::*;
 
+mod segment;
+mod trie;
+
+use segment::segment;
+use trie::Trie;
+
 type ParsedInput = (Vec<String>, Vec<String>);
 
 fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--segments") {
+        return print_segments(&parsed);
+    }
+
     let result1 = calculate_p1(&parsed);
     println!("Result p1: {}", result1);
 
-    let result2 = calculate_p2(&parsed);
+    let result2 = calculate_p2(&parsed)?;
     println!("Result p2: {}", result2);
 
     Ok(())
@@ -32,42 +45,42 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
 
 fn calculate_p1(input: &ParsedInput) -> usize {
     let (towels, designs) = input;
+    let trie = Trie::new(towels);
 
     designs
         .into_par_iter()
-        .filter(|design| count_possible_designs(design, 0, &towels, &mut HashMap::new())> 0)
+        .filter(|design| segment::<u64>(design, &trie).example.is_some())
         .count()
 }
 
-fn calculate_p2(input: &ParsedInput) -> usize {
+fn calculate_p2(input: &ParsedInput) -> anyhow::Result<u64> {
     let (towels, designs) = input;
+    let trie = Trie::new(towels);
 
-    designs
+    let counts: Vec<u64> = designs
         .into_par_iter()
-        .map(|design| count_possible_designs(design, 0, towels, &mut HashMap::new()))
-        .sum()
+        .map(|design| segment(design, &trie).count)
+        .collect::<Result<_, _>>()?;
+
+    Ok(checked_sum(counts)?)
 }
 
-fn count_possible_designs(design: &str, depth: usize, towels: &[String], memo: &mut HashMap<usize, usize>) -> usize {
+fn print_segments(input: &ParsedInput) -> anyhow::Result<()> {
+    let (towels, designs) = input;
+    let trie = Trie::new(towels);
 
-    if let Some(result) = memo.get(&depth) {
-        return *result;
-    }
+    println!("{} distinct towels", trie.len());
 
-    let mut n_arrangements = 0;
+    for design in designs {
+        let result = segment::<BigUint>(design, &trie);
+        let fewest = result.fewest.map_or("impossible".to_owned(), |pieces| {
+            pieces.iter().map(|p| trie.pattern(*p)).join(" ")
+        });
 
-    for towel in towels.iter() {
-        if design.starts_with(towel) {
-            if towel.len() < design.len() {
-                n_arrangements += count_possible_designs(&design[towel.len()..], depth + towel.len(), towels, memo);
-            } else {
-                n_arrangements += 1;
-            }
-        }
+        println!("{}: {} ways, fewest: {}", design, result.count?, fewest);
     }
 
-    memo.insert(depth, n_arrangements);
-    n_arrangements
+    Ok(())
 }
 
 
@@ -100,7 +113,7 @@ mod tests {
     #[case(load_sample("input.txt")?)]
     fn test_sample_p2(#[case] (parsed, _, expected): (ParsedInput, Option<u64>, Option<u64>)) -> anyhow::Result<()> {
 
-        let result2 = calculate_p2(&parsed);
+        let result2 = calculate_p2(&parsed)?;
 
         assert_eq!(expected, Some(result2 as u64));
         Ok(())
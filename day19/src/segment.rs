@@ -0,0 +1,126 @@
+use aoc_tools::{Count, CountOverflow};
+
+use crate::trie::Trie;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation<C> {
+    /// Number of different ways to build the design from patterns.
+    pub count: Result<C, CountOverflow>,
+    /// Pattern indices of one way to build it, if there is any.
+    pub example: Option<Vec<usize>>,
+    /// Pattern indices of a way using as few pieces as possible.
+    pub fewest: Option<Vec<usize>>,
+}
+
+/// Splits `design` into patterns, working from the end of the design towards its start
+/// so every position only looks at the already finished positions behind it.
+/// Overflowing counts only spoil `count`, the decompositions are still found.
+pub fn segment<C: Count>(design: &str, trie: &Trie) -> Segmentation<C> {
+    let design = design.as_bytes();
+    let len = design.len();
+
+    // per position: ways to finish the design from there (None once they overflow),
+    // and (pieces, first pattern) of the shortest way
+    let mut count: Vec<Option<C>> = vec![Some(C::zero()); len + 1];
+    let mut fewest: Vec<Option<(usize, usize)>> = vec![None; len + 1];
+    let mut example: Vec<Option<usize>> = vec![None; len + 1];
+
+    count[len] = Some(C::one());
+    fewest[len] = Some((0, usize::MAX));
+
+    for pos in (0..len).rev() {
+        for pattern in trie.prefixes_of(&design[pos..]) {
+            let next = pos + trie.pattern(pattern).len();
+            if fewest[next].is_none() {
+                continue;
+            }
+
+            count[pos] = match (&count[pos], &count[next]) {
+                (Some(c), Some(n)) => c.checked_add(n),
+                _ => None,
+            };
+            example[pos].get_or_insert(pattern);
+
+            let pieces = fewest[next].map(|(p, _)| p + 1).unwrap();
+            if fewest[pos].is_none_or(|(p, _)| pieces < p) {
+                fewest[pos] = Some((pieces, pattern));
+            }
+        }
+    }
+
+    Segmentation {
+        count: count.swap_remove(0).ok_or(CountOverflow),
+        example: follow(trie, len, |pos| example[pos]),
+        fewest: follow(trie, len, |pos| fewest[pos].map(|(_, pattern)| pattern)),
+    }
+}
+
+fn follow<F>(trie: &Trie, len: usize, choice: F) -> Option<Vec<usize>>
+    where F: Fn(usize) -> Option<usize>
+{
+    let mut pieces = Vec::new();
+    let mut pos = 0;
+
+    while pos < len {
+        let pattern = choice(pos)?;
+        pieces.push(pattern);
+        pos += trie.pattern(pattern).len();
+    }
+
+    Some(pieces)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+    use rstest::rstest;
+    use super::*;
+
+    const TOWELS: [&str; 8] = ["r", "wr", "b", "g", "bwu", "rb", "gb", "br"];
+
+    fn joined(trie: &Trie, pieces: Option<Vec<usize>>) -> Option<String> {
+        pieces.map(|p| p.iter().map(|i| trie.pattern(*i)).collect::<Vec<_>>().join(","))
+    }
+
+    #[rstest]
+    #[case("brwrr", 2, Some("br,wr,r"))]
+    #[case("rrbgbr", 6, Some("r,rb,g,br"))]
+    #[case("bwurrg", 1, Some("bwu,r,r,g"))]
+    #[case("ubwu", 0, None)]
+    #[case("", 1, Some(""))]
+    fn test_segment(#[case] design: &str, #[case] count: u64, #[case] fewest: Option<&str>) {
+        let trie = Trie::new(&TOWELS);
+
+        let result = segment(design, &trie);
+
+        assert_eq!(result.count, Ok(count));
+        assert_eq!(joined(&trie, result.fewest), fewest.map(str::to_owned));
+        assert_eq!(joined(&trie, result.example).map(|e| e.replace(',', "")), (count > 0).then(|| design.to_owned()));
+    }
+
+    #[test]
+    fn test_long_design() {
+        let trie = Trie::new(&["c", "ab", "a"]);
+        let design = "abc".repeat(20_000) + "a";
+
+        let result = segment::<u64>(&design, &trie);
+
+        assert_eq!(result.count, Ok(1));
+        assert_eq!(result.fewest.map(|f| f.len()), Some(40_001));
+    }
+
+    #[test]
+    fn test_count_overflow() {
+        // every "aa" can also be two "a", so the count follows the Fibonacci numbers
+        let trie = Trie::new(&["a", "aa"]);
+        let design = "a".repeat(200);
+
+        let narrow = segment::<u64>(&design, &trie);
+        let big = segment::<BigUint>(&design, &trie);
+
+        assert_eq!(narrow.count, Err(CountOverflow));
+        assert_eq!(narrow.fewest.map(|f| f.len()), Some(100));
+        assert_eq!(big.count.map(|c| c.bits()), Ok(139));
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+    pattern: Option<usize>,
+}
+
+/// Prefix tree over a set of patterns, so all patterns starting at a position of a text
+/// are found in a single walk instead of comparing every pattern.
+#[derive(Debug)]
+pub struct Trie {
+    nodes: Vec<TrieNode>,
+    patterns: Vec<String>,
+}
+
+impl Trie {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut trie = Self {
+            nodes: vec![TrieNode::default()],
+            patterns: Vec::new(),
+        };
+
+        for pattern in patterns {
+            trie.insert(pattern.as_ref());
+        }
+
+        trie
+    }
+
+    /// Adds a pattern and returns its index, duplicates keep the index of the first one.
+    pub fn insert(&mut self, pattern: &str) -> usize {
+        let mut node = 0;
+
+        for b in pattern.bytes() {
+            node = match self.nodes[node].children.get(&b) {
+                Some(child) => *child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(b, child);
+                    child
+                },
+            };
+        }
+
+        *self.nodes[node].pattern.get_or_insert_with(|| {
+            self.patterns.push(pattern.to_owned());
+            self.patterns.len() - 1
+        })
+    }
+
+    pub fn pattern(&self, idx: usize) -> &str {
+        &self.patterns[idx]
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Indices of all patterns found at the start of `text`, shortest first.
+    pub fn prefixes_of<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        text
+            .iter()
+            .scan(0, |node, b| {
+                *node = *self.nodes[*node].children.get(b)?;
+                Some(*node)
+            })
+            .filter_map(|node| self.nodes[node].pattern)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use super::*;
+
+    #[test]
+    fn test_prefixes() {
+        let mut trie = Trie::new(&["r", "wr", "b", "br", "bwu", "b"]);
+
+        assert_eq!(trie.len(), 5);
+        assert_eq!(trie.insert("wr"), 1);
+        assert_eq!(trie.insert("bw"), 5);
+
+        let found = trie.prefixes_of(b"bwurr").map(|p| trie.pattern(p)).collect_vec();
+        assert_eq!(found, vec!["b", "bw", "bwu"]);
+        assert_eq!(trie.prefixes_of(b"gbr").count(), 0);
+        assert_eq!(trie.prefixes_of(b"").count(), 0);
+    }
+}
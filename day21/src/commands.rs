@@ -1,4 +1,4 @@
-use aoc_tools::{Direction, Point};
+use aoc_tools::Direction;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
@@ -6,18 +6,18 @@ pub enum Command {
     Activate,
 }
 
-pub const NUMERIC_A: Point = Point { x: 2, y: 3 };
-pub const NUMERIC_F: Point = Point { x: 0, y: 3 };
+/// Keys of a directional keypad, every keypad controlling another one needs all of them.
+pub const CONTROL_KEYS: [char; 5] = ['^', '>', 'v', '<', 'A'];
 
-pub const DIRECTIONAL_A: Point = Point { x: 2, y: 0 };
-pub const DIRECTIONAL_F: Point = Point { x: 0, y: 0 };
-
-pub fn commands_to_string(cmds: &[Command]) -> String {
-    cmds.iter().map(|cmd| match cmd {
-        Command::Move(Direction::Up) => '^',
-        Command::Move(Direction::Right) => '>',
-        Command::Move(Direction::Down) => 'v',
-        Command::Move(Direction::Left) => '<',
-        Command::Activate => 'A',
-    }).collect()
+impl Command {
+    /// The key on a directional keypad that issues this command.
+    pub fn key(self) -> char {
+        match self {
+            Command::Move(Direction::Up) => '^',
+            Command::Move(Direction::Right) => '>',
+            Command::Move(Direction::Down) => 'v',
+            Command::Move(Direction::Left) => '<',
+            Command::Activate => 'A',
+        }
+    }
 }
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use aoc_tools::{InvalidInput, Point};
+
+/// Marks a position without a key, which the robot arm must never point at.
+pub const GAP: char = '#';
+
+pub const NUMERIC_LAYOUT: &str = "789\n456\n123\n#0A";
+pub const DIRECTIONAL_LAYOUT: &str = "#^A\n<v>";
+
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    keys: HashMap<char, Point>,
+    gaps: Vec<Point>,
+    size: (usize, usize),
+}
+
+impl Keypad {
+    pub fn from_layout(layout: &str) -> Result<Self, InvalidInput> {
+        let mut keys = HashMap::new();
+        let mut gaps = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+
+        for (y, row) in layout.lines().enumerate() {
+            let row_width = row.chars().count();
+            if *width.get_or_insert(row_width) != row_width {
+                return InvalidInput::err(row);
+            }
+
+            for (x, key) in row.chars().enumerate() {
+                let pos = Point { x, y };
+                if key == GAP {
+                    gaps.push(pos);
+                } else if keys.insert(key, pos).is_some() {
+                    return InvalidInput::err_char(key);
+                }
+            }
+            height += 1;
+        }
+
+        if !keys.contains_key(&'A') {
+            return InvalidInput::err(layout);
+        }
+
+        Ok(Self {
+            keys,
+            gaps,
+            size: (width.unwrap_or(0), height),
+        })
+    }
+
+    pub fn numeric() -> Self {
+        Self::from_layout(NUMERIC_LAYOUT).unwrap()
+    }
+
+    pub fn directional() -> Self {
+        Self::from_layout(DIRECTIONAL_LAYOUT).unwrap()
+    }
+
+    pub fn position(&self, key: char) -> Option<Point> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn key_at(&self, pos: Point) -> Option<char> {
+        self.keys
+            .iter()
+            .find(|(_, p)| **p == pos)
+            .map(|(k, _)| *k)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = char> + Clone + '_ {
+        self.keys.keys().copied()
+    }
+
+    pub fn is_gap(&self, pos: Point) -> bool {
+        self.gaps.contains(&pos)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_layout() {
+        let keypad = Keypad::numeric();
+
+        assert_eq!(keypad.size(), (3, 4));
+        assert_eq!(keypad.position('A'), Some(Point { x: 2, y: 3 }));
+        assert_eq!(keypad.position('7'), Some(Point { x: 0, y: 0 }));
+        assert_eq!(keypad.key_at(Point { x: 1, y: 3 }), Some('0'));
+        assert!(keypad.is_gap(Point { x: 0, y: 3 }));
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        assert!(Keypad::from_layout("12\n3").is_err());
+        assert!(Keypad::from_layout("1A\nA2").is_err());
+        assert!(Keypad::from_layout("12\n34").is_err());
+    }
+}
@@ -1,14 +1,18 @@
-use aoc_tools::{Direction, Point};
+use aoc_tools::{Direction, InvalidInput, ResultExt};
 use itertools::Itertools;
-use std::{collections::HashMap, usize};
+use std::collections::HashMap;
 
 mod c2d;
 mod commands;
+mod keypad;
 
-use commands::{commands_to_string, Command, DIRECTIONAL_A};
+use commands::{Command, CONTROL_KEYS};
+use keypad::Keypad;
 
 type ParsedInput = Vec<String>;
 
+type Costs = HashMap<(char, char), usize>;
+
 fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
@@ -27,68 +31,31 @@ fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
 }
 
 fn calculate_p1(input: &ParsedInput) -> anyhow::Result<usize> {
-    calculate_p_x(input, 2)
-}
-
-
-fn prepare_numpad_transitions() -> HashMap<(char, char), String> {
-    let all_digits = ['A', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
-
-    let mut transitions: HashMap<(char, char), String> = HashMap::new();
-
-    for start in all_digits.iter() {
-        for end in all_digits.iter() {
-            let start_point = digit_to_numeric_key(*start);
-            let end_point = digits_to_numeric_keys(&end.to_string());
-
-            let num_dist = distances_between_points(&start_point, &end_point);
-
-            let lencmd = eval_key_distances(&num_dist, |c| c2d::commands_on_numeric_pad(c, start_point).is_ok());
-
-            transitions.insert((*start, *end), lencmd);
-        }
-    }
-
-    transitions
+    calculate_p_x(input, &keypad_chain(2))
 }
 
-fn prepare_directional_transitions() -> HashMap<(char, char), String>{
-    let all_buttons = ['A', '>', '<', '^', 'v'];
-
-    let mut transitions: HashMap<(char, char), String> = HashMap::new();
-
-    for start in all_buttons.iter() {
-        for end in all_buttons.iter() {
-            let start_point = c2d::directional_button_to_command(*start);
-            let end_point = c2d::commands_from_string(&end.to_string());
-            let start_key = command_to_directional_key(&start_point);
-            let end_key = commands_to_directional_keys(&end_point);
-
-            let dir_dist = distances_between_points(&start_key, &end_key);
-
-            let lencmd = eval_key_distances(&dir_dist, |c|c2d::commands_on_directional_pad(c, start_key).is_ok());
-
-            transitions.insert((*start, *end), lencmd);
-        }
-    }
-
-    transitions
+fn calculate_p2(input: &ParsedInput) -> anyhow::Result<usize> {
+    calculate_p_x(input, &keypad_chain(25))
 }
 
-fn calculate_p2(input: &ParsedInput) -> anyhow::Result<usize> {
-    calculate_p_x(input, 25)
+/// Door's numeric keypad, then the directional keypads of the robots in between,
+/// and finally the directional keypad I'm typing on.
+fn keypad_chain(middle_bots: usize) -> Vec<Keypad> {
+    Some(Keypad::numeric())
+        .into_iter()
+        .chain(std::iter::repeat_n(Keypad::directional(), middle_bots + 1))
+        .collect()
 }
 
-fn calculate_p_x(input: &ParsedInput, middle_bots: usize) -> anyhow::Result<usize> {
+fn calculate_p_x(input: &ParsedInput, keypads: &[Keypad]) -> anyhow::Result<usize> {
 
-    let transitions = prepare_numpad_transitions();
-    let dir_transitions = prepare_directional_transitions();
+    let costs = prepare_chain_costs(keypads)?;
 
     let mut totals = 0;
 
     for digits in input.iter() {
         let code: usize = digits[..3].parse().unwrap();
-        let n_steps = calculate_cmd_len_v2(&transitions, &dir_transitions, digits, middle_bots);
+        let n_steps = sequence_cost(&costs, digits.chars()).map_err_to_invalid_input(digits)?;
 
         totals += n_steps * code;
     }
@@ -96,108 +63,76 @@ fn calculate_p_x(input: &ParsedInput, middle_bots: usize) -> anyhow::Result<usiz
     Ok(totals)
 }
 
-fn calculate_cmd_len_v2(transitions: &HashMap<(char, char), String>, dir_transitions: &HashMap<(char, char), String>, digits: &str, middle_bots: usize) -> usize {
-    let mut all_commands: HashMap<(char, char), usize> = HashMap::new();
+fn prepare_chain_costs(keypads: &[Keypad]) -> anyhow::Result<Costs> {
+    let (typed, robots) = keypads.split_last().ok_or(InvalidInput("empty keypad chain".to_owned()))?;
 
-    for nft in Some('A').into_iter().chain(digits.chars()).tuple_windows() {
-        let num_level = transitions.get(&nft).unwrap();
-        let mut level: HashMap<(char, char), usize> = HashMap::new();
-
-        for ft in Some('A').into_iter().chain(num_level.chars()).tuple_windows() {
-            *level.entry(ft).or_insert(0) += 1;
+    // every keypad but the door's presses keys on the next one down the chain
+    for (idx, controller) in keypads.iter().enumerate().skip(1) {
+        let missing: String = CONTROL_KEYS.iter().filter(|k| controller.position(**k).is_none()).collect();
+        if !missing.is_empty() {
+            Err(InvalidInput(format!("keypad {} controls another keypad, but lacks the keys {}", idx, missing)))?;
         }
+    }
 
-        for _ in 0..middle_bots {
-            let mut next_level: HashMap<(char, char), usize> = HashMap::new();
-            for (ft, cnt) in level.into_iter() {
-                let dir_level = dir_transitions.get(&ft).unwrap();
-                for nft in Some('A').into_iter().chain(dir_level.chars()).tuple_windows() {
-                    *next_level.entry(nft).or_insert(0) += cnt;
-                }
-            }
-            level = next_level;
-        }
+    // keys on my own keypad take just a single press each
+    let mut costs: Costs = typed
+        .keys()
+        .cartesian_product(typed.keys())
+        .map(|ft| (ft, 1))
+        .collect();
 
-        for (ft, cnt) in level.into_iter() {
-            *all_commands.entry(ft).or_insert(0) += cnt;
-        }
+    for keypad in robots.iter().rev() {
+        costs = prepare_transitions(keypad, &costs);
     }
 
-    all_commands.values().sum()
+    Ok(costs)
 }
 
-fn eval_key_distances<F>(distances: &[(isize, isize)], validator: F) -> String
-    where F: Fn(&[Command]) -> bool
-{
-    let mut result: Option<String> = None;
-    let mut shortest = usize::MAX;
-
-    for cmds in all_commands_from_distances(&distances).into_iter() {
-        if !validator(&cmds) { continue;}
-        let keys2 = commands_to_directional_keys(&cmds);
-        let distances2 = distances_between_points(&DIRECTIONAL_A, &keys2);
-
-        for cmds2 in all_commands_from_distances(&distances2) {
-                if c2d::commands_on_directional_pad(&cmds2, DIRECTIONAL_A).is_err() { continue;}
-                let keys3 = commands_to_directional_keys(&cmds2);
-                let distances3 = distances_between_points(&DIRECTIONAL_A, &keys3);
-                for cmds3 in all_commands_from_distances(&distances3) {
-                    if c2d::commands_on_directional_pad(&cmds3, DIRECTIONAL_A).is_err() { continue;}
-                    if cmds3.len() < shortest {
-                        shortest = cmds3.len();
-                        result = Some(commands_to_string(&cmds));
-                    }
-                }
+/// Cost of moving between each pair of keys along the command sequence that is cheapest
+/// to type on the keypad controlling this one, with `controller_costs` already counted down the chain.
+fn prepare_transitions(keypad: &Keypad, controller_costs: &Costs) -> Costs {
+    let mut transitions = Costs::new();
+
+    for start in keypad.keys() {
+        for end in keypad.keys() {
+            let best = key_paths(keypad, start, end)
+                .iter()
+                .filter_map(|cmds| sequence_cost(controller_costs, cmds.iter().map(|c| c.key())))
+                .min();
+
+            if let Some(cost) = best {
+                transitions.insert((start, end), cost);
+            }
         }
     }
-    result.unwrap()
-}
-
 
-fn commands_to_directional_keys(commands: &[Command]) -> Vec<Point> {
-    commands.iter().map(command_to_directional_key).collect()
+    transitions
 }
 
-fn command_to_directional_key(command: &Command) -> Point {
-    match command {
-        Command::Move(Direction::Up) => Point { x: 1, y: 0 },
-        Command::Move(Direction::Right) => Point { x: 2, y: 1 },
-        Command::Move(Direction::Down) => Point { x: 1, y: 1 },
-        Command::Move(Direction::Left) => Point { x: 0, y: 1 },
-        Command::Activate => Point { x: 2, y: 0 },
-    }
+/// Presses needed to type `keys`, starting with the arm on `A`.
+fn sequence_cost<I: IntoIterator<Item = char>>(costs: &Costs, keys: I) -> Option<usize> {
+    Some('A')
+        .into_iter()
+        .chain(keys)
+        .tuple_windows()
+        .map(|ft| costs.get(&ft))
+        .sum()
 }
 
-fn digits_to_numeric_keys(digits: &str) -> Vec<Point> {
-    digits.chars().map(digit_to_numeric_key).collect()
-}
+fn key_paths(keypad: &Keypad, start: char, end: char) -> Vec<Vec<Command>> {
+    let (Some(from), Some(to)) = (keypad.position(start), keypad.position(end)) else {
+        return Vec::new();
+    };
 
-fn digit_to_numeric_key(digit: char) -> Point {
-    match digit {
-        '1' => Point { x: 0, y: 2 },
-        '2' => Point { x: 1, y: 2 },
-        '3' => Point { x: 2, y: 2 },
-        '4' => Point { x: 0, y: 1 },
-        '5' => Point { x: 1, y: 1 },
-        '6' => Point { x: 2, y: 1 },
-        '7' => Point { x: 0, y: 0 },
-        '8' => Point { x: 1, y: 0 },
-        '9' => Point { x: 2, y: 0 },
-        '0' => Point { x: 1, y: 3 },
-        'A' => Point { x: 2, y: 3 },
-        _ => panic!("Invalid digit: {}", digit),
-    }
-}
+    let distance = (to.x as isize - from.x as isize, to.y as isize - from.y as isize);
 
-fn distances_between_points(start: &Point, points: &[Point]) -> Vec<(isize, isize)> {
-    Some(start)
+    all_commands_from_distance(distance)
         .into_iter()
-        .chain(points.into_iter())
-        .tuple_windows()
-        .map(|(p1, p2)| (p2.x as isize - p1.x as isize, p2.y as isize - p1.y as isize))
+        .filter(|cmds| c2d::commands_on_keypad(cmds, keypad, start).is_ok())
         .collect()
 }
 
+#[cfg(test)]
 fn all_commands_from_distances(distances: &[(isize, isize)]) -> Vec<Vec<Command>> {
     let mut output = Vec::new();
 
@@ -219,7 +154,6 @@ fn all_commands_from_distances(distances: &[(isize, isize)]) -> Vec<Vec<Command>
     output
 }
 
-#[allow(dead_code)]
 fn all_commands_from_distance(distance: (isize, isize)) -> Vec<Vec<Command>> {
     let mut output = Vec::new();
 
@@ -300,6 +234,42 @@ mod tests {
         Ok(())
     }
 
+    #[rstest]
+    #[case(0, 4)]
+    #[case(1, 12)]
+    #[case(2, 28)]
+    #[case(3, 68)]
+    fn test_keypad_chain_length(#[case] robots: usize, #[case] expected: usize) -> anyhow::Result<()> {
+        let keypads: Vec<_> = keypad_chain(2).into_iter().take(robots + 1).collect();
+
+        let costs = prepare_chain_costs(&keypads)?;
+
+        assert_eq!(sequence_cost(&costs, "029A".chars()), Some(expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_layout() -> anyhow::Result<()> {
+        let keypads = [Keypad::from_layout("A1\n#2")?, Keypad::directional()];
+
+        let costs = prepare_chain_costs(&keypads)?;
+
+        // >A vA <^A
+        assert_eq!(sequence_cost(&costs, "12A".chars()), Some(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_controller_not_directional() -> anyhow::Result<()> {
+        let keypads = [Keypad::numeric(), Keypad::from_layout("#^A\n<v#")?];
+
+        let err = prepare_chain_costs(&keypads).unwrap_err();
+
+        assert!(err.to_string().contains("keypad 1 controls another keypad, but lacks the keys >"));
+        assert!(prepare_chain_costs(&[Keypad::numeric(), Keypad::numeric()]).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_all_commands_from_distance_1_1() {
         let result = all_commands_from_distance((1, 1));
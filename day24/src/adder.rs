@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use aoc_tools::{int_to_wires, wires_to_int, Circuit, InvalidInput, NodeOp};
+use itertools::Itertools;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub bit: usize,
+    pub wire: usize,
+    pub rule: &'static str,
+}
+
+impl Violation {
+    pub fn describe(&self, circuit: &Circuit) -> String {
+        format!("bit {:02}: {}: {}", self.bit, circuit.name(self.wire), self.rule)
+    }
+}
+
+type GateIndex = HashMap<(&'static str, usize, usize), usize>;
+
+/// Matches every bit against the ripple-carry adder template: a half adder at bit 0,
+/// full adders above it, and the final carry driving the top output bit.
+pub fn check_adder(circuit: &Circuit) -> anyhow::Result<Vec<Violation>> {
+    let xnames = circuit.bus("x");
+    let ynames = circuit.bus("y");
+    let znames = circuit.bus("z");
+
+    if xnames.len() != ynames.len() || znames.len() != xnames.len() + 1 {
+        Err(InvalidInput(format!("bus widths x: {}, y: {}, z: {}", xnames.len(), ynames.len(), znames.len())))?
+    }
+
+    let gates = index_gates(circuit);
+    let find = |op: &'static str, a: usize, b: usize| gates.get(&(op, a.min(b), a.max(b))).copied();
+
+    let mut violations = Vec::new();
+    let mut carry: Option<usize> = None;
+
+    for bit in 0..xnames.len() {
+        let (x, y, z) = (xnames[bit], ynames[bit], znames[bit]);
+
+        let (Some(half_sum), Some(half_carry)) = (find("XOR", x, y), find("AND", x, y)) else {
+            violations.push(Violation { bit, wire: x, rule: "input bits are not combined by both XOR and AND" });
+            carry = None;
+            continue;
+        };
+
+        if bit == 0 {
+            if half_sum != z {
+                violations.push(Violation { bit, wire: z, rule: "output is not XOR of the input bits" });
+            }
+            carry = Some(half_carry);
+            continue;
+        }
+
+        let Some(carry_in) = carry else {
+            // nothing to compare against, pick up the carry chain again from this bit's OR gate
+            carry = find_consumer(circuit, half_carry, "OR");
+            continue;
+        };
+
+        match find("XOR", half_sum, carry_in) {
+            Some(sum) if sum == z => {},
+            Some(sum) => {
+                violations.push(Violation { bit, wire: z, rule: "output is not driven by the sum XOR" });
+                violations.push(Violation { bit, wire: sum, rule: "sum XOR does not drive the output" });
+            },
+            None => {
+                match circuit.node(z).map(|n| n.op) {
+                    Some(NodeOp::Xor(a0, a1)) if a0 == half_sum || a1 == half_sum => {
+                        violations.push(Violation { bit, wire: carry_in, rule: "carry does not feed the sum XOR" });
+                    },
+                    Some(NodeOp::Xor(a0, a1)) if a0 == carry_in || a1 == carry_in => {
+                        violations.push(Violation { bit, wire: half_sum, rule: "half sum does not feed the sum XOR" });
+                    },
+                    _ => {
+                        violations.push(Violation { bit, wire: z, rule: "output is not XOR of half sum and carry" });
+                    },
+                }
+            },
+        }
+
+        let carry_and = find("AND", half_sum, carry_in);
+        let carry_out = carry_and.and_then(|d| find("OR", half_carry, d));
+
+        match (carry_and, carry_out) {
+            (None, _) => {
+                violations.push(Violation { bit, wire: half_sum, rule: "half sum and carry are not combined by AND" });
+            },
+            (Some(and), None) => {
+                // either carry may be the misplaced one, so both gates and the OR are suspects
+                violations.push(Violation { bit, wire: half_carry, rule: "carries are not combined by OR" });
+                violations.push(Violation { bit, wire: and, rule: "carry AND does not feed the carry OR" });
+                if let Some(or) = find_consumer(circuit, half_carry, "OR") {
+                    violations.push(Violation { bit, wire: or, rule: "carry OR does not combine both carries" });
+                }
+            },
+            _ => {},
+        }
+
+        carry = carry_out.or_else(|| find_consumer(circuit, half_carry, "OR"));
+    }
+
+    let top_bit = xnames.len();
+    let top = znames[top_bit];
+    match carry {
+        Some(c) if c == top => {},
+        Some(c) => {
+            violations.push(Violation { bit: top_bit, wire: top, rule: "top output is not the final carry" });
+            violations.push(Violation { bit: top_bit, wire: c, rule: "final carry does not drive the top output" });
+        },
+        None => {
+            violations.push(Violation { bit: top_bit, wire: top, rule: "top output is not the final carry" });
+        },
+    }
+
+    Ok(violations)
+}
+
+/// Swaps gate outputs pairwise, fixing the lowest violated bit each time, until the
+/// circuit matches the adder template. Returns the repaired circuit and the swapped wires.
+pub fn repair_adder(circuit: &Circuit) -> anyhow::Result<(Circuit, Vec<usize>)> {
+    let mut circuit = circuit.clone();
+    let mut swaps = Vec::new();
+
+    let gate_outputs: Vec<usize> = circuit
+        .nodes()
+        .iter()
+        .filter(|(_, n)| !matches!(n.op, NodeOp::Const(_)))
+        .map(|(w, _)| *w)
+        .sorted()
+        .collect();
+
+    loop {
+        let violations = check_adder(&circuit)?;
+        let Some(bad_bit) = first_bad_bit(&violations) else {
+            return Ok((circuit, swaps));
+        };
+
+        let suspects: Vec<usize> = violations
+            .iter()
+            .filter(|v| v.bit == bad_bit)
+            .map(|v| v.wire)
+            .filter(|w| gate_outputs.contains(w))
+            .unique()
+            .collect();
+
+        let best = best_swap(&mut circuit, &suspects, &gate_outputs, bad_bit)?;
+
+        let Some((s, other)) = best else {
+            let report = violations
+                .iter()
+                .map(|v| v.describe(&circuit))
+                .join("\n");
+            Err(anyhow::anyhow!("Could not repair the circuit:\n{}", report))?
+        };
+
+        circuit.swap_outputs(s, other);
+        swaps.push(s);
+        swaps.push(other);
+    }
+}
+
+/// The swap of a suspect with any gate output that moves the first bad bit up the furthest.
+/// Swaps that close a loop in a loop-free circuit are never taken, such a circuit can't add
+/// at all. A circuit that already loops prefers swaps that open the loop.
+fn best_swap(circuit: &mut Circuit, suspects: &[usize], gate_outputs: &[usize], bad_bit: usize) -> anyhow::Result<Option<(usize, usize)>> {
+    let was_acyclic = circuit.topological_order().is_ok();
+    let mut best: Option<((bool, usize), usize, usize)> = None;
+
+    for (s, other) in suspects.iter().cartesian_product(gate_outputs.iter()) {
+        if s == other {
+            continue;
+        }
+
+        circuit.swap_outputs(*s, *other);
+        let acyclic = circuit.topological_order().is_ok();
+        let reached = if acyclic || !was_acyclic {
+            Some(first_bad_bit(&check_adder(circuit)?).unwrap_or(usize::MAX))
+        } else {
+            None
+        };
+        circuit.swap_outputs(*s, *other);
+
+        if let Some(reached) = reached.filter(|r| *r > bad_bit) {
+            let score = (acyclic, reached);
+            if best.is_none_or(|(b, _, _)| score > b) {
+                best = Some((score, *s, *other));
+            }
+        }
+    }
+
+    Ok(best.map(|(_, s, other)| (s, other)))
+}
+
+/// Bits where adding goes wrong, probed with the single bit alone, doubled so it carries
+/// into the next bit, and as the end of a carry rippling up from bit 0.
+pub fn find_defective_bits(circuit: &Circuit, xnames: &[usize], ynames: &[usize], znames: &[usize]) -> Vec<usize> {
+    // a looped circuit can not add anything
+    let Ok(order) = circuit.topological_order() else {
+        return (0..xnames.len()).collect();
+    };
+
+    let mut defective = Vec::new();
+
+    for bit in 0..xnames.len() {
+        let single: u64 = 1 << bit;
+        let probes = [(single, 0), (single, single), (single - 1, 1)];
+
+        let broken = probes
+            .iter()
+            .any(|(x, y)| add_in_order(&order, *x, *y, circuit, xnames, ynames, znames) != x + y);
+        if broken {
+            defective.push(bit);
+        }
+    }
+
+    defective
+}
+
+fn add_in_order(order: &[usize], x: u64, y: u64, circuit: &Circuit, xnames: &[usize], ynames: &[usize], znames: &[usize]) -> u64 {
+    let mut wires = circuit.wires();
+
+    int_to_wires(&mut wires, xnames, x);
+    int_to_wires(&mut wires, ynames, y);
+    circuit.evaluate_in(order, &mut wires);
+
+    wires_to_int(&wires, znames)
+}
+
+fn first_bad_bit(violations: &[Violation]) -> Option<usize> {
+    violations.iter().map(|v| v.bit).min()
+}
+
+fn index_gates(circuit: &Circuit) -> GateIndex {
+    circuit
+        .nodes()
+        .iter()
+        .filter_map(|(w, n)| match n.op.inputs()[..] {
+            [a0, a1] => Some(((n.op.name(), a0.min(a1), a0.max(a1)), *w)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn find_consumer(circuit: &Circuit, wire: usize, op_name: &str) -> Option<usize> {
+    circuit
+        .nodes()
+        .iter()
+        .find(|(_, n)| n.op.name() == op_name && n.op.has_input(wire))
+        .map(|(w, _)| *w)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use aoc_tools::{NameRegistry, Node};
+    use super::*;
+
+    fn add_gate(name_reg: &mut NameRegistry, nodes: &mut HashMap<usize, Node>, name: String, op: NodeOp) -> usize {
+        let id = name_reg.add_or_lookup(name);
+        nodes.insert(id, Node { op });
+        id
+    }
+
+    fn ripple_adder(width: usize) -> Circuit {
+        let mut name_reg = NameRegistry::new();
+        let mut nodes = HashMap::new();
+        let reg = &mut name_reg;
+        let nodes_ref = &mut nodes;
+
+        let mut carry = 0;
+        for bit in 0..width {
+            let x = add_gate(reg, nodes_ref, format!("x{:02}", bit), NodeOp::Const(false));
+            let y = add_gate(reg, nodes_ref, format!("y{:02}", bit), NodeOp::Const(false));
+
+            let z = format!("z{:02}", bit);
+            if bit == 0 {
+                add_gate(reg, nodes_ref, z, NodeOp::Xor(x, y));
+                carry = add_gate(reg, nodes_ref, format!("c{:02}", bit), NodeOp::And(x, y));
+                continue;
+            }
+
+            let half_sum = add_gate(reg, nodes_ref, format!("s{:02}", bit), NodeOp::Xor(x, y));
+            let half_carry = add_gate(reg, nodes_ref, format!("h{:02}", bit), NodeOp::And(y, x));
+            add_gate(reg, nodes_ref, z, NodeOp::Xor(carry, half_sum));
+            let carry_and = add_gate(reg, nodes_ref, format!("d{:02}", bit), NodeOp::And(half_sum, carry));
+            let carry_name = if bit == width - 1 { format!("z{:02}", width) } else { format!("c{:02}", bit) };
+            carry = add_gate(reg, nodes_ref, carry_name, NodeOp::Or(half_carry, carry_and));
+        }
+
+        let names: Vec<String> = name_reg.into();
+        Circuit::new(names.into_boxed_slice(), nodes)
+    }
+
+    fn add_numbers(x: u64, y: u64, circuit: &Circuit, xnames: &[usize], ynames: &[usize], znames: &[usize]) -> Option<u64> {
+        let order = circuit.topological_order().ok()?;
+
+        Some(add_in_order(&order, x, y, circuit, xnames, ynames, znames))
+    }
+
+    fn swapped(circuit: &Circuit, pairs: &[(&str, &str)]) -> Circuit {
+        let mut circuit = circuit.clone();
+        for (a, b) in pairs {
+            circuit.swap_outputs(circuit.lookup(a).unwrap(), circuit.lookup(b).unwrap());
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_valid_adder() -> anyhow::Result<()> {
+        let circuit = ripple_adder(8);
+
+        assert_eq!(check_adder(&circuit)?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_violations_named() -> anyhow::Result<()> {
+        let circuit = swapped(&ripple_adder(8), &[("z03", "d03")]);
+
+        let report: Vec<_> = check_adder(&circuit)?
+            .iter()
+            .map(|v| v.describe(&circuit))
+            .collect();
+
+        assert_eq!(report, vec![
+            "bit 03: z03: output is not driven by the sum XOR",
+            "bit 03: d03: sum XOR does not drive the output",
+            "bit 03: h03: carries are not combined by OR",
+            "bit 03: z03: carry AND does not feed the carry OR",
+            "bit 03: c03: carry OR does not combine both carries",
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_any_width() -> anyhow::Result<()> {
+        let original = ripple_adder(12);
+        let circuit = swapped(&original, &[("z02", "c02"), ("s05", "h05"), ("z07", "d07"), ("z09", "s09"), ("z11", "z12")]);
+
+        let (repaired, swaps) = repair_adder(&circuit)?;
+
+        let names: Vec<_> = swaps.iter().map(|s| repaired.name(*s)).sorted().collect();
+        assert_eq!(names, vec!["c02", "d07", "h05", "s05", "s09", "z02", "z07", "z09", "z11", "z12"]);
+
+        let (x, y, z) = (repaired.bus("x"), repaired.bus("y"), repaired.bus("z"));
+        assert_eq!(find_defective_bits(&repaired, &x, &y, &z), vec![]);
+        assert_eq!(add_numbers(0xabc, 0xfed, &repaired, &x, &y, &z), Some(0xabc + 0xfed));
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_misplaced_carry() -> anyhow::Result<()> {
+        // the carry AND of bit 1 ended up on bit 2, some swaps past bit 1 would close a loop
+        let mut circuit = swapped(&ripple_adder(4), &[("d01", "h02")]);
+        let (x, y, z) = (circuit.bus("x"), circuit.bus("y"), circuit.bus("z"));
+        let outputs: Vec<usize> = circuit
+            .nodes()
+            .iter()
+            .filter(|(_, n)| !matches!(n.op, NodeOp::Const(_)))
+            .map(|(w, _)| *w)
+            .collect();
+        let violations = check_adder(&circuit)?;
+        let bad_bit = first_bad_bit(&violations).unwrap();
+        let suspects: Vec<usize> = violations.iter().filter(|v| v.bit == bad_bit).map(|v| v.wire).collect();
+
+        let advancing_loops = suspects
+            .iter()
+            .cartesian_product(outputs.iter())
+            .filter(|(s, o)| {
+                let mut trial = circuit.clone();
+                trial.swap_outputs(**s, **o);
+                s != o && trial.topological_order().is_err()
+                    && first_bad_bit(&check_adder(&trial).unwrap()).is_none_or(|b| b > bad_bit)
+            })
+            .count();
+        assert!(advancing_loops > 0);
+        assert_eq!(find_defective_bits(&circuit, &x, &y, &z), vec![2, 3]);
+
+        let (s, other) = best_swap(&mut circuit, &suspects, &outputs, bad_bit)?.unwrap();
+        let mut trial = circuit.clone();
+        trial.swap_outputs(s, other);
+        assert!(trial.topological_order().is_ok());
+
+        let (repaired, swaps) = repair_adder(&circuit)?;
+        let names: Vec<_> = swaps.iter().map(|s| repaired.name(*s)).sorted().collect();
+        assert_eq!(names, vec!["d01", "h02"]);
+        assert_eq!(find_defective_bits(&repaired, &x, &y, &z), vec![]);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use aoc_tools::{int_to_wires, wires_to_int, Circuit, InvalidInput};
+use itertools::Itertools;
+
+/// Circuits with at most this many input bits in total are checked for every input combination.
+const EXHAUSTIVE_BITS: usize = 16;
+
+/// Buses are handled as u64, shifts by 64 or more would overflow.
+const MAX_BUS_WIDTH: usize = 63;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub x: u64,
+    pub y: u64,
+    pub expected: u64,
+    pub actual: Option<u64>,
+    pub differing: Vec<usize>,
+}
+
+impl Counterexample {
+    pub fn describe(&self, circuit: &Circuit) -> String {
+        let znames = circuit.bus("z");
+        let actual = self.actual.map_or("loop".to_owned(), |a| a.to_string());
+        let wires = self.differing
+            .iter()
+            .map(|bit| circuit.name(znames[*bit]))
+            .join(",");
+
+        format!("x={} y={}: expected {}, got {} (wrong bits: {})", self.x, self.y, self.expected, actual, wires)
+    }
+}
+
+/// Compares the x, y -> z behaviour of the circuit with `reference`, trying every input
+/// for small circuits and otherwise corner cases followed by `samples` random inputs.
+/// Buses wider than 63 bits are rejected.
+pub fn find_counterexample<F>(circuit: &Circuit, reference: F, seed: u64, samples: usize) -> Result<Option<Counterexample>, InvalidInput>
+    where F: Fn(u64, u64) -> u64
+{
+    let xnames = circuit.bus("x");
+    let ynames = circuit.bus("y");
+    let znames = circuit.bus("z");
+
+    let widest = xnames.len().max(ynames.len()).max(znames.len());
+    if widest > MAX_BUS_WIDTH {
+        return Err(InvalidInput(format!("buses of {} bits, at most {} are supported", widest, MAX_BUS_WIDTH)));
+    }
+
+    let x_mask = bit_mask(xnames.len());
+    let y_mask = bit_mask(ynames.len());
+    let z_mask = bit_mask(znames.len());
+
+    let inputs: Box<dyn Iterator<Item = (u64, u64)>> = if xnames.len() + ynames.len() <= EXHAUSTIVE_BITS {
+        Box::new((0..=x_mask).cartesian_product(0..=y_mask))
+    } else {
+        let mut rng = SplitMix64(seed);
+        let random = (0..samples).map(move |_| (rng.next() & x_mask, rng.next() & y_mask));
+        Box::new(corner_cases(xnames.len()).map(move |(x, y)| (x & x_mask, y & y_mask)).chain(random))
+    };
+
+    // a looped circuit computes nothing, the first input already is a counterexample
+    let order = circuit.topological_order().ok();
+
+    for (x, y) in inputs {
+        let mut wires = circuit.wires();
+        int_to_wires(&mut wires, &xnames, x);
+        int_to_wires(&mut wires, &ynames, y);
+
+        let expected = reference(x, y) & z_mask;
+        let actual = order.as_ref().map(|order| {
+            circuit.evaluate_in(order, &mut wires);
+            wires_to_int(&wires, &znames)
+        });
+
+        if actual != Some(expected) {
+            let diff = actual.map_or(z_mask, |a| a ^ expected);
+            return Ok(Some(Counterexample {
+                x,
+                y,
+                expected,
+                actual,
+                differing: (0..znames.len()).filter(|bit| diff & (1 << bit) != 0).collect(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn corner_cases(width: usize) -> impl Iterator<Item = (u64, u64)> {
+    let ones = bit_mask(width);
+    let alternating = [0x5555_5555_5555_5555, 0xaaaa_aaaa_aaaa_aaaa];
+
+    let patterns = [
+        (0, 0),
+        (ones, 0),
+        (0, ones),
+        (ones, ones),
+        (alternating[0], alternating[1]),
+        (alternating[1], alternating[0]),
+        (alternating[0], alternating[0]),
+        (alternating[1], alternating[1]),
+    ];
+
+    // single bits, and carries rippling from each bit up to the top
+    let bits = (0..width).flat_map(move |bit| [
+        (1 << bit, 0),
+        (0, 1 << bit),
+        (1 << bit, 1 << bit),
+        (ones << bit, 1 << bit),
+        (ones >> bit, 1),
+    ]);
+
+    patterns.into_iter().chain(bits)
+}
+
+fn bit_mask(width: usize) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use aoc_tools::{NameRegistry, Node, NodeOp};
+    use super::*;
+
+    fn bitwise(width: usize, op: fn(usize, usize) -> NodeOp) -> Circuit {
+        let mut name_reg = NameRegistry::new();
+        let mut nodes = HashMap::new();
+
+        for bit in 0..width {
+            let x = name_reg.add_or_lookup(format!("x{:02}", bit));
+            let y = name_reg.add_or_lookup(format!("y{:02}", bit));
+            let z = name_reg.add_or_lookup(format!("z{:02}", bit));
+            nodes.insert(z, Node { op: op(x, y) });
+        }
+
+        let names: Vec<String> = name_reg.into();
+        Circuit::new(names.into_boxed_slice(), nodes)
+    }
+
+    #[test]
+    fn test_exhaustive_and() {
+        let circuit = bitwise(4, NodeOp::And);
+
+        assert_eq!(find_counterexample(&circuit, |x, y| x & y, 0, 0), Ok(None));
+    }
+
+    #[test]
+    fn test_exhaustive_counterexample() {
+        let circuit = bitwise(4, NodeOp::Xor);
+
+        let counter = find_counterexample(&circuit, |x, y| x + y, 0, 0).unwrap().unwrap();
+
+        assert_eq!(counter, Counterexample { x: 1, y: 1, expected: 2, actual: Some(0), differing: vec![1] });
+        assert_eq!(counter.describe(&circuit), "x=1 y=1: expected 2, got 0 (wrong bits: z01)");
+    }
+
+    #[test]
+    fn test_random_wide() {
+        let circuit = bitwise(40, NodeOp::Or);
+
+        assert_eq!(find_counterexample(&circuit, |x, y| x | y, 42, 1000), Ok(None));
+
+        let counter = find_counterexample(&circuit, |x, y| x ^ y, 42, 1000).unwrap().unwrap();
+        assert_eq!((counter.x, counter.y), (bit_mask(40), bit_mask(40)));
+        assert_eq!(counter.differing, (0..40).collect_vec());
+    }
+
+    #[test]
+    fn test_too_wide() {
+        assert!(find_counterexample(&bitwise(63, NodeOp::Or), |x, y| x | y, 42, 10).is_ok());
+        assert!(find_counterexample(&bitwise(64, NodeOp::Or), |x, y| x | y, 42, 10).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use aoc_tools::{Circuit, NodeOp};
+use itertools::Itertools;
+
+pub const INPUT_BUSES: [&str; 2] = ["x", "y"];
+pub const OUTPUT_BUS: &str = "z";
+
+#[derive(Debug, Default)]
+pub struct Highlight {
+    pub defective: Vec<usize>,
+    pub swapped: Vec<usize>,
+}
+
+/// Graphviz source for the circuit, optionally colouring defective and swapped wires.
+pub fn to_dot(circuit: &Circuit, highlight: Option<&Highlight>) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "digraph circuit {{").unwrap();
+    writeln!(out, "    rankdir=LR;").unwrap();
+
+    let inputs = INPUT_BUSES.iter().flat_map(|b| circuit.bus(b));
+    writeln!(out, "    {{ rank=min; {} }}", dot_ids(circuit, inputs)).unwrap();
+    writeln!(out, "    {{ rank=max; {} }}", dot_ids(circuit, circuit.bus(OUTPUT_BUS).into_iter())).unwrap();
+
+    for wire in 0..circuit.names().len() {
+        let name = circuit.name(wire);
+        let (label, shape) = match circuit.node(wire).map(|n| n.op) {
+            None | Some(NodeOp::Const(_)) => (name.to_owned(), "circle"),
+            Some(op) => (format!("{}\\n{}", name, op.name()), gate_shape(op)),
+        };
+
+        let fill = match highlight {
+            Some(h) if h.swapped.contains(&wire) => ", style=filled, fillcolor=red",
+            Some(h) if h.defective.contains(&wire) => ", style=filled, fillcolor=orange",
+            _ => "",
+        };
+
+        writeln!(out, "    \"{}\" [label=\"{}\", shape={}{}];", name, label, shape, fill).unwrap();
+    }
+
+    for (wire, node) in circuit.nodes().iter().sorted_by_key(|(w, _)| circuit.name(**w)) {
+        for input in node.op.inputs() {
+            writeln!(out, "    \"{}\" -> \"{}\";", circuit.name(input), circuit.name(*wire)).unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+pub fn to_netlist(circuit: &Circuit) -> String {
+    let mut out = String::new();
+    let bus_bits = bus_bits(circuit);
+
+    let ports = INPUT_BUSES
+        .iter()
+        .map(|b| (*b, "input"))
+        .chain([(OUTPUT_BUS, "output")])
+        .filter_map(|(b, dir)| {
+            let width = circuit.bus(b).len();
+            (width > 0).then(|| format!("{} [{}:0] {}", dir, width - 1, b))
+        })
+        .join(", ");
+    writeln!(out, "module circuit({});", ports).unwrap();
+
+    let internal = circuit
+        .nodes()
+        .keys()
+        .filter(|w| !bus_bits.contains_key(w))
+        .map(|w| circuit.name(*w))
+        .sorted()
+        .join(", ");
+    if !internal.is_empty() {
+        writeln!(out, "    wire {};", internal).unwrap();
+    }
+
+    let order = circuit
+        .topological_order()
+        .unwrap_or_else(|_| circuit.nodes().keys().copied().sorted_by_key(|w| circuit.name(*w)).collect());
+
+    for wire in order {
+        let op = circuit.node(wire).unwrap().op;
+        if matches!(op, NodeOp::Const(_)) && is_input(&bus_bits, wire) {
+            // input bits are ports, their values are not part of the circuit
+            continue;
+        }
+
+        let w = |v: usize| netlist_name(circuit, &bus_bits, v);
+        let expr = match op {
+            NodeOp::Const(val) => format!("1'b{}", val as u8),
+            NodeOp::Not(a0) => format!("~{}", w(a0)),
+            NodeOp::And(a0, a1) => format!("{} & {}", w(a0), w(a1)),
+            NodeOp::Or(a0, a1) => format!("{} | {}", w(a0), w(a1)),
+            NodeOp::Xor(a0, a1) => format!("{} ^ {}", w(a0), w(a1)),
+            NodeOp::Nand(a0, a1) => format!("~({} & {})", w(a0), w(a1)),
+            NodeOp::Nor(a0, a1) => format!("~({} | {})", w(a0), w(a1)),
+            NodeOp::Xnor(a0, a1) => format!("~({} ^ {})", w(a0), w(a1)),
+        };
+
+        writeln!(out, "    assign {} = {};", w(wire), expr).unwrap();
+    }
+
+    writeln!(out, "endmodule").unwrap();
+    out
+}
+
+fn gate_shape(op: NodeOp) -> &'static str {
+    match op {
+        NodeOp::Const(_) => "circle",
+        NodeOp::Not(_) => "triangle",
+        NodeOp::And(_, _) | NodeOp::Nand(_, _) => "box",
+        NodeOp::Or(_, _) | NodeOp::Nor(_, _) => "ellipse",
+        NodeOp::Xor(_, _) | NodeOp::Xnor(_, _) => "diamond",
+    }
+}
+
+fn dot_ids<I>(circuit: &Circuit, wires: I) -> String
+    where I: Iterator<Item = usize>
+{
+    wires
+        .map(|w| format!("\"{}\";", circuit.name(w)))
+        .join(" ")
+}
+
+/// Bus and bit of every wire that belongs to one of the ports.
+type BusBits = HashMap<usize, (&'static str, usize)>;
+
+fn bus_bits(circuit: &Circuit) -> BusBits {
+    INPUT_BUSES
+        .iter()
+        .chain([&OUTPUT_BUS])
+        .flat_map(|b| circuit.bus(b).into_iter().enumerate().map(|(bit, w)| (w, (*b, bit))))
+        .collect()
+}
+
+fn is_input(bus_bits: &BusBits, wire: usize) -> bool {
+    matches!(bus_bits.get(&wire), Some((b, _)) if INPUT_BUSES.contains(b))
+}
+
+fn netlist_name(circuit: &Circuit, bus_bits: &BusBits, wire: usize) -> String {
+    match bus_bits.get(&wire) {
+        Some((bus, bit)) => format!("{}[{}]", bus, bit),
+        None => circuit.name(wire).to_owned(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use aoc_tools::{NameRegistry, Node};
+    use super::*;
+
+    fn half_adder() -> Circuit {
+        let mut name_reg = NameRegistry::new();
+        let x = name_reg.add_or_lookup("x00");
+        let y = name_reg.add_or_lookup("y00");
+        let s = name_reg.add_or_lookup("z00");
+        let c = name_reg.add_or_lookup("z01");
+
+        let nodes = HashMap::from([
+            (x, Node { op: NodeOp::Const(true) }),
+            (y, Node { op: NodeOp::Const(false) }),
+            (s, Node { op: NodeOp::Xor(x, y) }),
+            (c, Node { op: NodeOp::And(x, y) }),
+        ]);
+
+        let names: Vec<String> = name_reg.into();
+        Circuit::new(names.into_boxed_slice(), nodes)
+    }
+
+    #[test]
+    fn test_netlist() {
+        let netlist = to_netlist(&half_adder());
+
+        assert_eq!(netlist, "module circuit(input [0:0] x, input [0:0] y, output [1:0] z);\n\
+                             \x20   assign z[0] = x[0] ^ y[0];\n\
+                             \x20   assign z[1] = x[0] & y[0];\n\
+                             endmodule\n");
+    }
+
+    #[test]
+    fn test_dot_highlight() {
+        let highlight = Highlight {
+            defective: vec![3],
+            swapped: vec![2],
+        };
+
+        let dot = to_dot(&half_adder(), Some(&highlight));
+
+        assert!(dot.contains("{ rank=min; \"x00\"; \"y00\"; }"));
+        assert!(dot.contains("{ rank=max; \"z00\"; \"z01\"; }"));
+        assert!(dot.contains("\"z00\" [label=\"z00\\nXOR\", shape=diamond, style=filled, fillcolor=red];"));
+        assert!(dot.contains("\"z01\" [label=\"z01\\nAND\", shape=box, style=filled, fillcolor=orange];"));
+        assert!(dot.contains("\"x00\" -> \"z01\";"));
+    }
+
+    #[test]
+    fn test_dot_plain() {
+        let dot = to_dot(&half_adder(), None);
+
+        assert!(dot.contains("\"z00\" [label=\"z00\\nXOR\", shape=diamond];"));
+        assert!(!dot.contains("fillcolor"));
+    }
+}
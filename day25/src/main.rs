@@ -1,12 +1,21 @@
-use aoc_tools::{IterMoreTools, InvalidInput, ResultExt};
+use aoc_tools::{IterMoreTools, InvalidInput};
 use anyhow::anyhow;
+use std::env;
 
-type ParsedInput = (Vec<[usize; 5]>, Vec<[usize; 5]>);
+mod schematic;
+
+use schematic::{Kind, KeyIndex, Schematic};
+
+type ParsedInput = (Vec<Schematic>, Vec<Schematic>);
 
 fn main() -> anyhow::Result<()> {
     let input = aoc_tools::Input::from_cmd()?;
     let parsed = parse_input(input)?;
 
+    if env::args().nth(2).as_deref() == Some("--verbose") {
+        print_pairs(&parsed);
+    }
+
     let result1 = calculate_p1(&parsed)?;
     println!("Result p1: {}", result1);
 
@@ -19,48 +28,45 @@ fn main() -> anyhow::Result<()> {
 fn parse_input(input: aoc_tools::Input) -> anyhow::Result<ParsedInput> {
     let lines = input.read_lines()?;
 
-    let mut locks: Vec<[usize; 5]> = Vec::new();
-    let mut keys: Vec<[usize; 5]> = Vec::new();
-
-
-    for block in lines.as_slice().split(|l| l == "") {
-        let b_type = block[0].chars().next().unwrap();
-        let mut counts = [0; 5];
-        for b_line in &block[1..block.len()-1] {
-            for (i, c) in b_line.chars().enumerate() {
-                if c == '#' {
-                    counts[i] += 1;
-                }
-            }
-        }
+    let schematics: Vec<Schematic> = lines
+        .split(|l| l.is_empty())
+        .filter(|block| !block.is_empty())
+        .map(Schematic::parse)
+        .try_collect_vec()?;
 
-        if b_type == '#' {
-            locks.push(counts);
-        } else {
-            keys.push(counts);
+    if let Some(first) = schematics.first() {
+        if let Some(odd) = schematics.iter().find(|s| !s.same_shape(first)) {
+            return Err(InvalidInput(format!("schematics of different shapes: {:?}", odd.heights)).into());
         }
     }
 
+    let (locks, keys) = schematics
+        .into_iter()
+        .partition(|s| s.kind == Kind::Lock);
+
     Ok((locks, keys))
 }
 
 fn calculate_p1(input: &ParsedInput) -> anyhow::Result<usize> {
     let (locks, keys) = input;
 
-    println!("L: {:?}\nK: {:?}", locks, keys);
+    let index = KeyIndex::new(keys);
+
+    Ok(locks
+        .iter()
+        .map(|lock| index.count_fitting(lock))
+        .sum())
+}
 
-    let mut matches = 0;
+fn print_pairs(input: &ParsedInput) {
+    let (locks, keys) = input;
 
     for lock in locks.iter() {
         for key in keys.iter() {
-            println!("{:?} vs {:?}", lock, key);
-            if lock.iter().zip(key.iter()).all(|(l, k)| l + k < 6) {
-                matches += 1;
-            }
+            let verdict = if lock.fits(key) { "fit" } else { "overlap" };
+            println!("lock {:?} and key {:?}: {}", lock.heights, key.heights, verdict);
         }
     }
-
-    Ok(matches)
 }
 
 fn calculate_p2(_input: &ParsedInput) -> anyhow::Result<u64> {
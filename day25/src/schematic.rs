@@ -0,0 +1,172 @@
+use aoc_tools::InvalidInput;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Lock,
+    Key,
+}
+
+/// A lock hangs down from a full top row, a key stands up on a full bottom row.
+/// `heights` leave out that full row, `space` is what's left between the two full rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    pub kind: Kind,
+    pub heights: Vec<usize>,
+    pub space: usize,
+}
+
+impl Schematic {
+    pub fn parse<S: AsRef<str>>(block: &[S]) -> Result<Self, InvalidInput> {
+        let rows: Vec<&[u8]> = block.iter().map(|r| r.as_ref().as_bytes()).collect();
+        let invalid = |why: &str| InvalidInput(format!("{}: {}", why, block.first().map_or("", |r| r.as_ref())));
+
+        let (Some(top), Some(bottom)) = (rows.first(), rows.last()) else {
+            return Err(invalid("empty schematic"));
+        };
+        if rows.len() < 2 || top.is_empty() {
+            return Err(invalid("schematic too small"));
+        }
+        if rows.iter().any(|r| r.len() != top.len()) {
+            return Err(invalid("rows of different width"));
+        }
+
+        let full = |row: &[u8], c: u8| row.iter().all(|b| *b == c);
+        let kind = if full(top, b'#') && full(bottom, b'.') {
+            Kind::Lock
+        } else if full(top, b'.') && full(bottom, b'#') {
+            Kind::Key
+        } else {
+            return Err(invalid("neither a full top nor a full bottom row"));
+        };
+
+        let mut heights = Vec::with_capacity(top.len());
+        for col in 0..top.len() {
+            let column: Vec<u8> = match kind {
+                Kind::Lock => rows.iter().map(|r| r[col]).collect(),
+                Kind::Key => rows.iter().rev().map(|r| r[col]).collect(),
+            };
+
+            let pins = column.iter().take_while(|b| **b == b'#').count();
+            if column[pins..].iter().any(|b| *b != b'.') {
+                return Err(invalid("pins with gaps or unknown characters"));
+            }
+            heights.push(pins - 1);
+        }
+
+        Ok(Self {
+            kind,
+            heights,
+            space: rows.len() - 2,
+        })
+    }
+
+    /// Whether a lock and a key of the same shape go together without overlapping.
+    pub fn fits(&self, other: &Self) -> bool {
+        self.kind != other.kind
+            && self.same_shape(other)
+            && self.heights.iter().zip(&other.heights).all(|(a, b)| a + b <= self.space)
+    }
+
+    pub fn same_shape(&self, other: &Self) -> bool {
+        self.heights.len() == other.heights.len() && self.space == other.space
+    }
+}
+
+/// Keys sorted into buckets by the height of each column, so a lock can pick all keys
+/// short enough in every column with a few bitset intersections.
+pub struct KeyIndex {
+    words: usize,
+    space: usize,
+    // [column][height]: keys with at most that height in the column
+    up_to: Vec<Vec<Vec<u64>>>,
+}
+
+impl KeyIndex {
+    /// All keys need to have the same shape.
+    pub fn new(keys: &[Schematic]) -> Self {
+        let words = keys.len().div_ceil(64);
+        let width = keys.first().map_or(0, |k| k.heights.len());
+        let space = keys.first().map_or(0, |k| k.space);
+
+        let mut up_to = vec![vec![vec![0u64; words]; space + 1]; width];
+        for (id, key) in keys.iter().enumerate() {
+            for (col, height) in key.heights.iter().enumerate() {
+                up_to[col][*height][id / 64] |= 1 << (id % 64);
+            }
+        }
+
+        for buckets in up_to.iter_mut() {
+            for h in 1..=space {
+                let (lower, higher) = buckets.split_at_mut(h);
+                for (w, l) in higher[0].iter_mut().zip(&lower[h - 1]) {
+                    *w |= l;
+                }
+            }
+        }
+
+        Self { words, space, up_to }
+    }
+
+    /// Number of keys that fit `lock`.
+    pub fn count_fitting(&self, lock: &Schematic) -> usize {
+        if self.up_to.is_empty() || lock.space != self.space || lock.heights.len() != self.up_to.len() {
+            return 0;
+        }
+
+        let mut fitting = vec![u64::MAX; self.words];
+        for (buckets, height) in self.up_to.iter().zip(&lock.heights) {
+            for (f, k) in fitting.iter_mut().zip(&buckets[self.space - height]) {
+                *f &= k;
+            }
+        }
+
+        fitting.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let lock = Schematic::parse(&["###", "#.#", "#..", "..."]).unwrap();
+        let key = Schematic::parse(&["...", "..#", "#.#", "###"]).unwrap();
+
+        assert_eq!(lock, Schematic { kind: Kind::Lock, heights: vec![2, 0, 1], space: 2 });
+        assert_eq!(key, Schematic { kind: Kind::Key, heights: vec![1, 0, 2], space: 2 });
+        assert!(!lock.fits(&key));
+    }
+
+    #[rstest]
+    #[case(&[])]
+    #[case(&["###"])]
+    #[case(&["###", "#.", "..."])]
+    #[case(&["###", "#.#", "###"])]
+    #[case(&["##.", "...", "..."])]
+    #[case(&["###", "..#", "#..", "..."])]
+    #[case(&["###", "#x#", "..."])]
+    fn test_invalid(#[case] block: &[&str]) {
+        assert!(Schematic::parse(block).is_err());
+    }
+
+    #[test]
+    fn test_index_matches_pairs() {
+        // random schematics of three columns with up to three pins
+        let mut seed = 7u32;
+        let mut next = || { seed = seed.wrapping_mul(1103515245).wrapping_add(12345); (seed >> 16) as usize % 4 };
+        let mut random = |kind| Schematic { kind, heights: vec![next(), next(), next()], space: 3 };
+
+        let locks: Vec<_> = (0..40).map(|_| random(Kind::Lock)).collect();
+        let keys: Vec<_> = (0..150).map(|_| random(Kind::Key)).collect();
+        let index = KeyIndex::new(&keys);
+
+        for lock in locks.iter() {
+            let pairwise = keys.iter().filter(|k| lock.fits(k)).count();
+            assert_eq!(index.count_fitting(lock), pairwise, "{:?}", lock);
+        }
+        assert_eq!(KeyIndex::new(&[]).count_fitting(&locks[0]), 0);
+    }
+}